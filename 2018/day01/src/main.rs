@@ -1,18 +1,151 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::env;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io;
+use std::io::{
+    self,
+    prelude::*,
+    BufReader,
+};
+use std::num::ParseIntError;
+
+// CLI arguments
+type Args = Vec<String>;
+
+// Frequency changes, in the order the device applies them
+type Changes = Vec<i64>;
+
+// A change that couldn't be parsed
+#[derive(Debug, PartialEq)]
+struct ChangeError {
+    line:   usize,
+    input:  String,
+    source: ParseIntError,
+}
+
+impl fmt::Display for ChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Line {}: Invalid change '{}': {}",
+            self.line,
+            self.input,
+            self.source,
+        )
+    }
+}
+
+impl Error for ChangeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Parse the frequency changes, one per line. Changes are +N, -N or N.
+fn parse_changes(input: &str) -> Result<Changes, ChangeError> {
+    input.lines()
+        .enumerate()
+        .map(|(count, line)| {
+            let line = line.trim();
+
+            line.parse().map_err(|source| ChangeError {
+                line:  count + 1,
+                input: line.to_string(),
+                source,
+            })
+        })
+        .collect()
+}
+
+// Frequency after applying every change once, starting from 0
+fn resulting_frequency(changes: &[i64]) -> i64 {
+    changes.iter().sum()
+}
+
+// First frequency reached twice while looping over the changes, or None if
+// no frequency ever repeats.
+//
+// After m full passes, the frequency at position k of the pass is
+// prefix[k] + m * drift, where drift is the change over a whole pass. A
+// later pass can only revisit prefix[j] from prefix[i] if the two share a
+// residue modulo the drift and prefix[i] is behind prefix[j] in the
+// direction of the drift. Grouping the prefix sums by residue and sorting
+// each group finds every such pair without simulating the passes.
+fn first_repeat(changes: &[i64]) -> Option<i64> {
+    // Frequencies reached during the first pass, including the start. The
+    // frequency at the end of the pass is the start of the next.
+    let mut prefix = Vec::with_capacity(changes.len() + 1);
+    let mut seen = HashSet::new();
+    let mut freq = 0;
+
+    prefix.push(freq);
+    seen.insert(freq);
+
+    for change in changes {
+        freq += change;
+
+        // Anything repeating within the first pass comes before any repeat
+        // involving a later pass.
+        if !seen.insert(freq) {
+            return Some(freq);
+        }
+
+        prefix.push(freq);
+    }
+
+    // The final frequency belongs to the next pass
+    let drift = prefix.pop()?;
+
+    // With no drift, every pass visits the same frequencies and the end of
+    // the first pass would have repeated the start.
+    if drift == 0 {
+        return None;
+    }
+
+    // Group the positions in a pass by the residue of their frequency
+    let mut residues: HashMap<i64, Vec<usize>> = HashMap::new();
+
+    for (i, freq) in prefix.iter().enumerate() {
+        residues.entry(freq.rem_euclid(drift.abs()))
+            .or_default()
+            .push(i);
+    }
 
-type FrequencyCount = HashMap<i64, i64>;
+    // Best candidate as (time of repeat, frequency)
+    let len = prefix.len() as i64;
+    let mut best: Option<(i64, i64)> = None;
 
-fn main() {
-    // Collect command line args
-    let args: Vec<String> = env::args().collect();
+    for positions in residues.values_mut() {
+        // Order positions by frequency in the direction of the drift, so
+        // each position is followed by the next frequency it will reach.
+        positions.sort_by_key(|&i| prefix[i] * drift.signum());
 
-    // If we got an arg, use it as the input, otherwise use stdin
-    let mut input: Box<io::Read> = if args.len() > 1 {
+        for pair in positions.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let passes = (prefix[to] - prefix[from]) / drift;
+            let time = passes * len + from as i64;
+
+            if best.is_none_or(|(t, _)| time < t) {
+                best = Some((time, prefix[to]));
+            }
+        }
+    }
+
+    best.map(|(_, freq)| freq)
+}
+
+// Get an input reader
+fn input_reader(
+    args: Args,
+) -> Result<BufReader<Box<dyn io::Read>>, Box<dyn Error>> {
+    // Either read from the given file or stdin
+    let input: Box<dyn io::Read> = if args.len() > 1 {
         let filename = &args[1];
-        let fh = File::open(filename).unwrap();
+        let fh = File::open(filename)?;
         Box::new(fh)
     }
     else {
@@ -20,62 +153,87 @@ fn main() {
         Box::new(stdin)
     };
 
-    // Read the input into a buffer
-    let mut buffer = String::new();
-    input.read_to_string(&mut buffer).unwrap();
+    let reader = BufReader::new(input);
 
-    // Frequency tracking
-    let mut freq: i64 = 0;
+    Ok(reader)
+}
 
-    // Map trackin which frequencies we've seen
-    let mut seen = FrequencyCount::new();
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = env::args().collect();
 
-    // Keep track of calibration status
-    let mut calibrated = false;
+    // Get the input
+    let mut buffer = String::new();
+    let mut reader = input_reader(args)?;
+    reader.read_to_string(&mut buffer)?;
 
-    // Tracking of the first loop to output day01a value
-    let mut first_loop = true;
+    let changes = parse_changes(&buffer)?;
 
-    // Loop over lines in buffer until we're calibrated
-    while !calibrated {
-        for line in buffer.lines() {
-            // Get a mutable version of the line so we can split_off
-            let mut line = line.to_owned();
+    // Frequency after a single pass, the day01a answer
+    println!("Freq: {}", resulting_frequency(&changes));
 
-            // Split number out of line, leaving sign
-            let num = line.split_off(1);
+    // Output calibration value
+    match first_repeat(&changes) {
+        Some(freq) => println!("Calibrated: {}", freq),
+        None       => println!("Calibrated: never, no frequency repeats"),
+    }
 
-            // Get the sign
-            let sign = line;
+    Ok(())
+}
 
-            // Convert number to an i64
-            let value: i64 = num.parse().unwrap();
+#[cfg(test)]
+mod test {
+    use super::*;
 
-            // Perform some math on the frequency depending on the sign
-            freq = match sign.as_ref() {
-                "+" => freq + value,
-                "-" => freq - value,
-                _ => { unreachable!() },
-            };
+    #[test]
+    fn test_resulting_frequency() {
+        // Taken from example on day 1
+        let tests = vec![
+            ("+1\n-2\n+3\n+1\n", 3),
+            ("+1\n+1\n+1\n", 3),
+            ("+1\n+1\n-2\n", 0),
+            ("-1\n-2\n-3\n", -6),
+        ];
 
-            // Maintain a hash of what we've seen
-            *seen.entry(freq).or_insert(0) += 1;
+        for (input, freq) in tests {
+            let changes = parse_changes(input).unwrap();
 
-            // Check if we've seen this frequency twice or not.
-            if Some(&2) == seen.get(&freq) {
-                // We have it, break out of the loop.
-                calibrated = true;
-                break;
-            }
+            assert_eq!(resulting_frequency(&changes), freq);
         }
+    }
 
-        // If this is the first loop, output the frequency answer for day01a.
-        if first_loop {
-            println!("Freq: {}", freq);
-            first_loop = false;
+    #[test]
+    fn test_first_repeat() {
+        // Taken from example on day 1, part 2
+        let tests = vec![
+            ("+1\n-2\n+3\n+1\n", Some(2)),
+            ("+1\n-1\n", Some(0)),
+            ("+3\n+3\n+4\n-2\n-4\n", Some(10)),
+            ("-6\n+3\n+8\n+5\n-6\n", Some(5)),
+            ("+7\n+7\n-2\n-7\n-4\n", Some(14)),
+            ("+1\n+1\n", None),
+            ("", None),
+        ];
+
+        for (input, freq) in tests {
+            let changes = parse_changes(input).unwrap();
+
+            assert_eq!(first_repeat(&changes), freq);
         }
     }
 
-    // Output calibration value
-    println!("Calibrated: {}", freq);
+    #[test]
+    fn test_parse_changes() {
+        assert_eq!(parse_changes("+1\n-2\n3\n"), Ok(vec![1, -2, 3]));
+
+        let err = parse_changes("+1\n+x\n").unwrap_err();
+
+        assert_eq!(err.line, 2);
+        assert_eq!(err.input, "+x");
+        assert_eq!(
+            err.to_string(),
+            "Line 2: Invalid change '+x': invalid digit found in string",
+        );
+
+        assert!(parse_changes("+1\n\n-1\n").is_err());
+    }
 }
@@ -1,32 +1,223 @@
-use std::collections::HashMap;
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    HashMap,
+    HashSet,
+};
 use std::env;
+use std::error::Error;
 use std::fs::File;
-use std::io;
+use std::io::{
+    self,
+    prelude::*,
+    BufReader,
+};
 
-type LetterCounts = HashMap<char, i64>;
-type Multipliers = HashMap<i64, i64>;
+// CLI arguments
+type Args = Vec<String>;
+
+// Repeat counts multiplied together for the puzzle checksum
+const CHECKSUM_REPEATS: [usize; 2] = [2, 3];
+
+// Map of letter to the number of times it appears in an ID
+type LetterCounts = BTreeMap<char, usize>;
+
+// Map of repeat count to the number of IDs with a letter appearing exactly
+// that many times.
+type RepeatTotals = BTreeMap<usize, usize>;
+
+// Histogram of the letters in an ID
+fn letter_counts(id: &str) -> LetterCounts {
+    let mut counts = LetterCounts::new();
+
+    for ch in id.chars() {
+        *counts.entry(ch).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+// Count the IDs having any letter repeated exactly each of the given number
+// of times. Every requested repeat count is present, even if no ID has it.
+fn repeat_totals(ids: &[&str], repeats: &[usize]) -> RepeatTotals {
+    let mut totals: RepeatTotals = repeats.iter()
+        .map(|&repeat| (repeat, 0))
+        .collect();
+
+    for id in ids {
+        let counts: BTreeSet<usize> = letter_counts(id)
+            .into_values()
+            .collect();
+
+        for (repeat, total) in totals.iter_mut() {
+            if counts.contains(repeat) {
+                *total += 1;
+            }
+        }
+    }
+
+    totals
+}
+
+// Product of the repeat totals for the given repeat counts. This is 0 if no
+// ID has one of the repeat counts, and 0 if no repeat counts are given.
+fn checksum(ids: &[&str], repeats: &[usize]) -> usize {
+    let totals = repeat_totals(ids, repeats);
+
+    if totals.is_empty() {
+        return 0;
+    }
+
+    totals.values().product()
+}
+
+// Check if two IDs differ in at most k positions, giving up as soon as they
+// don't. IDs of different lengths never match.
+fn within_distance(a: &str, b: &str, k: usize) -> bool {
+    if a.chars().count() != b.chars().count() {
+        return false;
+    }
 
-// Compare two given strings returning the numbers of characters that differ.
-fn compare(a: &str, b: &str) -> usize {
     let mut diff = 0;
 
-    for (i, c) in a.char_indices() {
-        if c != b.chars().nth(i).unwrap() {
+    for (x, y) in a.chars().zip(b.chars()) {
+        if x != y {
             diff += 1;
+
+            if diff > k {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+// Letters common to both IDs, in order
+fn common_letters(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .filter(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+// Find the first pair of IDs that differ by exactly one character.
+//
+// For each position, every ID is hashed with the character at that position
+// masked out. Two IDs differing only at that position produce the same key.
+fn differ_by_one<'a>(ids: &[&'a str]) -> Option<(&'a str, &'a str)> {
+    let longest = ids.iter()
+        .map(|id| id.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for position in 0 .. longest {
+        // Map of (before, after) the masked position to the ID
+        let mut seen: HashMap<(&str, &str), &str> = HashMap::new();
+
+        for id in ids {
+            let mut indices = id.char_indices().skip(position);
+
+            let start = match indices.next() {
+                Some((i, _)) => i,
+                None         => continue,
+            };
+
+            let end = indices.next().map_or(id.len(), |(i, _)| i);
+            let key = (&id[.. start], &id[end ..]);
+
+            match seen.get(&key) {
+                // Identical IDs aren't near-duplicates
+                Some(other) if other != id => return Some((other, id)),
+                Some(_)                    => {},
+                None                       => {
+                    seen.insert(key, id);
+                },
+            }
         }
     }
 
-    diff
+    None
+}
+
+// Split an ID into k + 1 blocks of roughly equal size
+fn blocks(id: &str, k: usize) -> Vec<&str> {
+    let indices: Vec<usize> = id.char_indices()
+        .map(|(i, _)| i)
+        .chain(Some(id.len()))
+        .collect();
+
+    let len = indices.len() - 1;
+    let count = k + 1;
+
+    (0 .. count)
+        .map(|n| {
+            let start = indices[n * len / count];
+            let end = indices[(n + 1) * len / count];
+
+            &id[start .. end]
+        })
+        .collect()
 }
 
-fn main() {
-    // Collect command line args
-    let args: Vec<String> = env::args().collect();
+// Find every pair of distinct IDs differing in at most k positions, as
+// indices into ids.
+//
+// If two IDs differ in at most k positions, then splitting both into k + 1
+// blocks leaves at least one block identical in each. Only IDs sharing a
+// block are compared.
+fn near_duplicates(ids: &[&str], k: usize) -> BTreeSet<(usize, usize)> {
+    // IDs can't differ in more positions than they have, so any larger k
+    // matches every pair of the same length anyway.
+    let longest = ids.iter()
+        .map(|id| id.chars().count())
+        .max()
+        .unwrap_or(0);
+    let k = k.min(longest);
+
+    // Map of (block number, length, block) to the IDs containing it
+    let mut buckets: HashMap<(usize, usize, &str), Vec<usize>> = HashMap::new();
+
+    for (i, id) in ids.iter().enumerate() {
+        let len = id.chars().count();
+
+        for (n, block) in blocks(id, k).into_iter().enumerate() {
+            buckets.entry((n, len, block))
+                .or_default()
+                .push(i);
+        }
+    }
 
-    // If we got an arg, use it as the input, otherwise use stdin
-    let mut input: Box<io::Read> = if args.len() > 1 {
+    let mut pairs = BTreeSet::new();
+    let mut checked = HashSet::new();
+
+    for bucket in buckets.values() {
+        for (n, &a) in bucket.iter().enumerate() {
+            for &b in &bucket[n + 1 ..] {
+                // Pairs can share more than one block
+                if ids[a] == ids[b] || !checked.insert((a, b)) {
+                    continue;
+                }
+
+                if within_distance(ids[a], ids[b], k) {
+                    pairs.insert((a, b));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+// Get an input reader
+fn input_reader(
+    args: Args,
+) -> Result<BufReader<Box<dyn io::Read>>, Box<dyn Error>> {
+    // Either read from the given file or stdin
+    let input: Box<dyn io::Read> = if args.len() > 1 {
         let filename = &args[1];
-        let fh = File::open(filename).unwrap();
+        let fh = File::open(filename)?;
         Box::new(fh)
     }
     else {
@@ -34,55 +225,157 @@ fn main() {
         Box::new(stdin)
     };
 
-    // Read the input into a buffer
-    let mut buffer = String::new();
-    input.read_to_string(&mut buffer).unwrap();
+    let reader = BufReader::new(input);
 
-    let mut totals = Multipliers::new();
+    Ok(reader)
+}
 
-    for line in buffer.lines() {
-        let mut counts = LetterCounts::new();
+fn main() -> Result<(), Box<dyn Error>> {
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
 
-        let mut two = false;
-        let mut three = false;
+    // List every pair of IDs within this distance of each other
+    let mut within = None;
 
-        // Count characters in the line
-        for ch in line.chars() {
-            *counts.entry(ch).or_insert(0) += 1;
-        }
+    // Print the letter histogram of each ID
+    let mut histogram = false;
 
-        for val in counts.values() {
-            match val {
-                2 => two = true,
-                3 => three = true,
-                _ => {},
-            }
-        }
+    // Repeat counts to multiply for the checksum
+    let mut repeats = CHECKSUM_REPEATS.to_vec();
 
-        if two {
-            *totals.entry(2).or_insert(0) += 1;
+    for flag in &flags {
+        if let Some(k) = flag.strip_prefix("--within=") {
+            within = Some(k.parse()?);
+        }
+        else if let Some(list) = flag.strip_prefix("--repeats=") {
+            repeats = list.split(',')
+                .map(|n| n.trim().parse())
+                .collect::<Result<_, _>>()?;
         }
+        else if flag == "--histogram" {
+            histogram = true;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
-        if three {
-            *totals.entry(3).or_insert(0) += 1;
+    // Read the input into a buffer
+    let mut buffer = String::new();
+    let mut reader = input_reader(args)?;
+    reader.read_to_string(&mut buffer)?;
+
+    let ids: Vec<&str> = buffer.lines().collect();
+
+    if let Some(k) = within {
+        for (a, b) in near_duplicates(&ids, k) {
+            println!("{} -> {}", ids[a], ids[b]);
         }
 
-        // Work out diffs
-        // line from above is A, each line in lines is B.
-        let lines = buffer.clone();
-        for b in lines.lines() {
-            let diff = compare(line, b);
+        return Ok(());
+    }
 
-            if diff == 1 {
-                println!("COMP: {} -> {}", line, b);
-            }
+    if histogram {
+        for id in &ids {
+            let counts: Vec<String> = letter_counts(id).iter()
+                .map(|(ch, count)| format!("{}={}", ch, count))
+                .collect();
+
+            println!("{}: {}", id, counts.join(" "));
         }
+
+        return Ok(());
+    }
+
+    println!("Checksum: {}", checksum(&ids, &repeats));
+
+    match differ_by_one(&ids) {
+        Some((a, b)) => println!("Common: {}", common_letters(a, b)),
+        None         => println!("Common: no IDs differ by one character"),
     }
 
-    // We now want to multiply the 2s by the 3s.
-    let twos = totals.get(&2).unwrap();
-    let threes = totals.get(&3).unwrap();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Taken from example on day 2
+    const CHECKSUM_EXAMPLE: [&str; 7] = [
+        "abcdef",
+        "bababc",
+        "abbcde",
+        "abcccd",
+        "aabcdd",
+        "abcdee",
+        "ababab",
+    ];
+
+    // Taken from example on day 2, part 2
+    const EXAMPLE: [&str; 7] = [
+        "abcde",
+        "fghij",
+        "klmno",
+        "pqrst",
+        "fguij",
+        "axcye",
+        "wvxyz",
+    ];
+
+    #[test]
+    fn test_checksum() {
+        let totals = repeat_totals(&CHECKSUM_EXAMPLE, &CHECKSUM_REPEATS);
+        let expected: RepeatTotals = vec![(2, 4), (3, 3)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(totals, expected);
+        assert_eq!(checksum(&CHECKSUM_EXAMPLE, &CHECKSUM_REPEATS), 12);
+        assert_eq!(checksum(&CHECKSUM_EXAMPLE, &[1, 2]), 24);
+        assert_eq!(checksum(&CHECKSUM_EXAMPLE, &[2, 4]), 0);
+        assert_eq!(checksum(&CHECKSUM_EXAMPLE, &[]), 0);
+        assert_eq!(checksum(&[], &CHECKSUM_REPEATS), 0);
+    }
 
-    let checksum = twos * threes;
-    println!("Checksum: {}", checksum);
+    #[test]
+    fn test_letter_counts() {
+        let counts = letter_counts("bababc");
+
+        assert_eq!(counts.get(&'a'), Some(&2));
+        assert_eq!(counts.get(&'b'), Some(&3));
+        assert_eq!(counts.get(&'c'), Some(&1));
+        assert_eq!(counts.len(), 3);
+    }
+
+    #[test]
+    fn test_differ_by_one() {
+        let (a, b) = differ_by_one(&EXAMPLE).unwrap();
+
+        assert_eq!((a, b), ("fghij", "fguij"));
+        assert_eq!(common_letters(a, b), "fgij");
+
+        assert_eq!(differ_by_one(&["abc", "abc", "xyz"]), None);
+        assert_eq!(differ_by_one(&["abc", "abcd"]), None);
+        assert_eq!(differ_by_one(&[]), None);
+    }
+
+    #[test]
+    fn test_near_duplicates() {
+        let pairs: Vec<(usize, usize)> = near_duplicates(&EXAMPLE, 1)
+            .into_iter()
+            .collect();
+
+        assert_eq!(pairs, vec![(1, 4)]);
+
+        let pairs: Vec<(usize, usize)> = near_duplicates(&EXAMPLE, 2)
+            .into_iter()
+            .collect();
+
+        assert_eq!(pairs, vec![(0, 5), (1, 4)]);
+        assert!(near_duplicates(&EXAMPLE, 0).is_empty());
+        assert_eq!(near_duplicates(&EXAMPLE, 5).len(), 21);
+        assert_eq!(near_duplicates(&EXAMPLE, usize::MAX).len(), 21);
+    }
 }
@@ -1,31 +1,96 @@
+use common::grid::{
+    Grid,
+    GridError,
+};
+use std::convert::TryFrom;
 use std::env;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io;
-use regex::{
-    Captures,
-    Regex,
+use std::io::{
+    self,
+    prelude::*,
+    BufReader,
+};
+use std::str::FromStr;
+use std::time::{
+    Duration,
+    Instant,
 };
 
-const CLAIM_PATTERN: &'static str = r"(?x)  # Ignore whitespace, allow comments
-    ^\#(?P<id>\d+)                          # Claim ID: id
-    \s@\s                                   # @
-    (?P<x>\d+),(?P<y>\d+):\s                # Anchor points: x,y
-    (?P<width>\d+)x(?P<height>\d+)$         # Rectangle size: WxH
-    ";
+// Heatmap colours, as RGB
+const EMPTY_COLOUR: [u8; 3] = [0, 0, 0];
+const SINGLE_COLOUR: [u8; 3] = [32, 48, 128];
+const OUTLINE_COLOUR: [u8; 3] = [0, 255, 0];
+
+// Most square inches the dense engine and heatmap will hold, 1GiB of counts
+const MAX_DENSE_SQUARE_INCHES: usize = 1 << 28;
+
+// Iterations to run each engine for when benchmarking
+const BENCH_ITERATIONS: u32 = 10;
 
-#[derive(Debug)]
+// CLI arguments
+type Args = Vec<String>;
+
+#[derive(Debug, PartialEq)]
 struct Rectangle {
     width: i64,
     height: i64,
 }
 
-#[derive(Debug)]
+impl Rectangle {
+    fn area(&self) -> i64 {
+        self.width * self.height
+    }
+
+    // Returns the anchor and size of the area covered by both this rectangle
+    // placed at anchor and the other rectangle placed at other_anchor, if
+    // there is one.
+    fn intersection(
+        &self,
+        anchor: &Anchor,
+        other: &Rectangle,
+        other_anchor: &Anchor,
+    ) -> Option<(Anchor, Rectangle)> {
+        let a = anchor.far_corner(self);
+        let b = other_anchor.far_corner(other);
+
+        let anchor = Anchor {
+            x: anchor.x.max(other_anchor.x),
+            y: anchor.y.max(other_anchor.y),
+        };
+
+        let rectangle = Rectangle {
+            width: a.x.min(b.x) - anchor.x,
+            height: a.y.min(b.y) - anchor.y,
+        };
+
+        if rectangle.width <= 0 || rectangle.height <= 0 {
+            return None;
+        }
+
+        Some((anchor, rectangle))
+    }
+}
+
+#[derive(Debug, PartialEq)]
 struct Anchor {
     x: i64,
     y: i64,
 }
 
-#[derive(Debug)]
+impl Anchor {
+    // The corner diagonally opposite the anchor of a rectangle placed here.
+    // This is exclusive, one past the last square inch covered.
+    fn far_corner(&self, r: &Rectangle) -> Anchor {
+        Anchor {
+            x: self.x + r.width,
+            y: self.y + r.height,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 struct Claim {
     id: i64,
     anchor: Anchor,
@@ -33,63 +98,494 @@ struct Claim {
 }
 
 impl Claim {
+    // The claim with every position and size multiplied by factor. Fails
+    // if the factor isn't positive, or the claim's corners or area would no
+    // longer fit in an i64.
+    fn scaled(&self, factor: i64) -> Result<Claim, Box<dyn Error>> {
+        if factor < 1 {
+            return Err(format!("Scale must be at least 1, got {}", factor)
+                .into());
+        }
+
+        let overflow = || {
+            format!("Scale {} overflows claim #{}", factor, self.id)
+        };
+        let scale = |n: i64| n.checked_mul(factor).ok_or_else(overflow);
+
+        let claim = Claim {
+            id: self.id,
+            anchor: Anchor {
+                x: scale(self.anchor.x)?,
+                y: scale(self.anchor.y)?,
+            },
+            rectangle: Rectangle {
+                width: scale(self.rectangle.width)?,
+                height: scale(self.rectangle.height)?,
+            },
+        };
+
+        // far_corner and area work with these
+        let (anchor, rectangle) = (&claim.anchor, &claim.rectangle);
+        let fits = anchor.x.checked_add(rectangle.width).is_some()
+            && anchor.y.checked_add(rectangle.height).is_some()
+            && rectangle.width.checked_mul(rectangle.height).is_some();
+
+        if !fits {
+            return Err(overflow().into());
+        }
+
+        Ok(claim)
+    }
+
+    fn far_corner(&self) -> Anchor {
+        self.anchor.far_corner(&self.rectangle)
+    }
+
+    // Returns the anchor and size of the area covered by both claims, if
+    // there is one.
+    fn intersection(&self, c: &Claim) -> Option<(Anchor, Rectangle)> {
+        self.rectangle.intersection(&self.anchor, &c.rectangle, &c.anchor)
+    }
+
     // Checks if any rectangle bounds overlap
     fn overlap(&self, c: &Claim) -> bool {
-        false
+        self.overlap_area(c) > 0
     }
 
     // Returns the size of the overlapping area
-    // NYI
+    fn overlap_area(&self, c: &Claim) -> i64 {
+        self.intersection(c).map_or(0, |(_, r)| r.area())
+    }
 }
 
-fn capture_to_i64(c: &Captures, name: &str) -> i64 {
-    c.name(name).unwrap().as_str().parse().unwrap()
+// The component of a claim that couldn't be parsed, with the offending text
+#[derive(Debug, PartialEq)]
+enum ClaimError {
+    Id(String),
+    At(String),
+    Anchor(String),
+    Colon(String),
+    Size(String),
+    Trailing(String),
 }
 
-fn process_input(mut inp: impl io::Read) -> Vec<Claim> {
-    // Read the input into a buffer
-    let mut buffer = String::new();
-    inp.read_to_string(&mut buffer).unwrap();
+impl fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (message, input) = match self {
+            Self::Id(s)       => ("Malformed claim ID, expected #id", s),
+            Self::At(s)       => ("Expected '@'", s),
+            Self::Anchor(s)   => ("Malformed anchor, expected x,y", s),
+            Self::Colon(s)    => ("Expected ':' after anchor", s),
+            Self::Size(s)     => ("Malformed size, expected WxH", s),
+            Self::Trailing(s) => ("Unexpected trailing input", s),
+        };
+
+        write!(f, "{}: '{}'", message, input)
+    }
+}
 
-    // Regex
-    let re = Regex::new(CLAIM_PATTERN).unwrap();
+impl Error for ClaimError {}
 
-    // Storage for processed claims.
-    let mut claims: Vec<Claim> = Vec::new();
+// Parse a number made only of digits, without a sign
+fn parse_digits<T: FromStr>(input: &str) -> Option<T> {
+    if !input.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    input.parse().ok()
+}
 
-    for line in buffer.lines() {
-        let captures = re.captures(line).unwrap();
+// Parse a pair of numbers separated by sep
+fn parse_pair(input: &str, sep: char) -> Option<(i64, i64)> {
+    let (a, b) = input.split_once(sep)?;
+    let a: u32 = parse_digits(a)?;
+    let b: u32 = parse_digits(b)?;
 
-        let r = Rectangle{
-            width: capture_to_i64(&captures, "width"),
-            height: capture_to_i64(&captures, "height"),
-        };
+    Some((a.into(), b.into()))
+}
+
+// Parses claims in the form: #id @ x,y: WxH
+impl FromStr for Claim {
+    type Err = ClaimError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let mut next = || parts.next().unwrap_or("");
+
+        let id = next();
+        // IDs are any non-negative number, unlike the geometry which must
+        // fit in a u32
+        let id = id.strip_prefix('#')
+            .and_then(parse_digits::<i64>)
+            .ok_or_else(|| ClaimError::Id(id.to_string()))?;
 
-        let a = Anchor{
-            x: capture_to_i64(&captures, "x"),
-            y: capture_to_i64(&captures, "y"),
+        let at = next();
+        if at != "@" {
+            return Err(ClaimError::At(at.to_string()));
+        }
+
+        let anchor = next();
+        let (x, y) = parse_pair(anchor.strip_suffix(':').unwrap_or(anchor), ',')
+            .ok_or_else(|| ClaimError::Anchor(anchor.to_string()))?;
+
+        if !anchor.ends_with(':') {
+            return Err(ClaimError::Colon(anchor.to_string()));
+        }
+
+        let size = next();
+        let (width, height) = parse_pair(size, 'x')
+            .ok_or_else(|| ClaimError::Size(size.to_string()))?;
+
+        let trailing = next();
+        if !trailing.is_empty() {
+            return Err(ClaimError::Trailing(trailing.to_string()));
+        }
+
+        Ok(Claim {
+            id,
+            anchor: Anchor { x, y },
+            rectangle: Rectangle { width, height },
+        })
+    }
+}
+
+// Number of claims covering each square inch of fabric, as a grid of rows
+// (y) and columns (x) large enough to hold every claim.
+struct Fabric(Grid<u32>);
+
+impl Fabric {
+    // Fails if the fabric would be too large to hold every square inch
+    fn new(claims: &[Claim]) -> Result<Self, Box<dyn Error>> {
+        let (width, height) = claims.iter()
+            .map(|c| c.far_corner())
+            .fold((0, 0), |(w, h), a| (w.max(a.x), h.max(a.y)));
+
+        let too_large = || {
+            format!(
+                "Fabric of {}x{} square inches is too large for the dense \
+                 engine or a heatmap",
+                width,
+                height,
+            )
         };
 
-        let c = Claim{
-            id: capture_to_i64(&captures, "id"),
-            anchor: a,
-            rectangle: r,
+        let rows = usize::try_from(height).map_err(|_| too_large())?;
+        let cols = usize::try_from(width).map_err(|_| too_large())?;
+
+        let fits = rows.checked_mul(cols)
+            .is_some_and(|size| size <= MAX_DENSE_SQUARE_INCHES);
+
+        if !fits {
+            return Err(too_large().into());
+        }
+
+        let mut grid = Grid::new(rows, cols, 0)?;
+
+        for claim in claims {
+            for (x, y) in Self::square_inches(claim) {
+                if let Some(count) = grid.get_mut(y, x) {
+                    *count += 1;
+                }
+            }
+        }
+
+        Ok(Self(grid))
+    }
+
+    // Positions of every square inch covered by a claim, as (x, y)
+    fn square_inches(claim: &Claim) -> impl Iterator<Item = (usize, usize)> {
+        let (left, top) = (claim.anchor.x, claim.anchor.y);
+        let corner = claim.far_corner();
+
+        (top .. corner.y).flat_map(move |y| {
+            (left .. corner.x).map(move |x| (x as usize, y as usize))
+        })
+    }
+
+    // Square inches covered by two or more claims
+    fn overlapping(&self) -> usize {
+        self.0.iter()
+            .filter(|(_, _, count)| **count > 1)
+            .count()
+    }
+
+    // Colour for a square inch covered by count claims. Overlaps go from
+    // yellow for two claims to red for the most claims on any square inch.
+    fn heat(count: u32, max: u32) -> [u8; 3] {
+        match count {
+            0 => EMPTY_COLOUR,
+            1 => SINGLE_COLOUR,
+            _ => {
+                let range = max.saturating_sub(2).max(1);
+                let t = (count - 2).min(range) * 255 / range;
+
+                [255, 255 - t as u8, 0]
+            },
+        }
+    }
+
+    // Render the claim counts as a binary PPM image, one pixel per square
+    // inch, with the outline of each given claim drawn over the top.
+    fn heatmap(&self, outlined: &[&Claim]) -> Result<Vec<u8>, GridError> {
+        let max = self.0.iter()
+            .map(|(_, _, count)| *count)
+            .max()
+            .unwrap_or(0);
+
+        let mut pixels = Grid::new(self.0.rows(), self.0.cols(), EMPTY_COLOUR)?;
+
+        for (y, x, count) in self.0.iter() {
+            pixels.set(y, x, Self::heat(*count, max));
+        }
+
+        for claim in outlined {
+            let corner = claim.far_corner();
+
+            for (x, y) in Self::square_inches(claim) {
+                let (x_i, y_i) = (x as i64, y as i64);
+                let edge = x_i == claim.anchor.x
+                    || y_i == claim.anchor.y
+                    || x_i == corner.x - 1
+                    || y_i == corner.y - 1;
+
+                if edge {
+                    pixels.set(y, x, OUTLINE_COLOUR);
+                }
+            }
+        }
+
+        let header = format!("P6\n{} {}\n255\n", pixels.cols(), pixels.rows());
+        let mut image = header.into_bytes();
+
+        for (_, _, pixel) in pixels.iter() {
+            image.extend_from_slice(pixel);
+        }
+
+        Ok(image)
+    }
+}
+
+// Ways of counting the overlapping square inches
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Engine {
+    // Count claims on every square inch of the fabric
+    Dense,
+
+    // Count claims on the cells between claim edges
+    Compressed,
+}
+
+impl TryFrom<&str> for Engine {
+    type Error = Box<dyn Error>;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        match input {
+            "dense"      => Ok(Self::Dense),
+            "compressed" => Ok(Self::Compressed),
+            _            => Err(format!("Unknown engine: {}", input).into()),
+        }
+    }
+}
+
+impl Engine {
+    fn overlapping(&self, claims: &[Claim]) -> Result<i64, Box<dyn Error>> {
+        match self {
+            Self::Dense      => Ok(Fabric::new(claims)?.overlapping() as i64),
+            Self::Compressed => CompressedFabric::new(claims)?.overlapping(),
+        }
+    }
+
+    // Claims that don't overlap any other claim
+    fn intact<'a>(
+        &self,
+        claims: &'a [Claim],
+    ) -> Result<Vec<&'a Claim>, Box<dyn Error>> {
+        match self {
+            Self::Dense      => Ok(intact_claims(claims)),
+            Self::Compressed => {
+                Ok(CompressedFabric::new(claims)?.intact(claims))
+            },
+        }
+    }
+}
+
+// Number of claims covering each cell of the fabric, using coordinate
+// compression.
+//
+// Only the claim edges matter, so the fabric is divided into cells between
+// consecutive edges on each axis. Claims are added to a difference grid over
+// these cells, which is then summed to give the claims covering each cell.
+// This depends on the number of claims, not the size of the fabric.
+struct CompressedFabric {
+    xs: Vec<i64>,
+    ys: Vec<i64>,
+    counts: Grid<i32>,
+}
+
+impl CompressedFabric {
+    fn new(claims: &[Claim]) -> Result<Self, GridError> {
+        let edges = |f: fn(&Claim) -> (i64, i64)| {
+            let mut edges: Vec<i64> = claims.iter()
+                .flat_map(|c| {
+                    let (start, end) = f(c);
+                    [start, end]
+                })
+                .collect();
+
+            edges.sort_unstable();
+            edges.dedup();
+            edges
         };
 
-        claims.push(c);
+        let xs = edges(|c| (c.anchor.x, c.far_corner().x));
+        let ys = edges(|c| (c.anchor.y, c.far_corner().y));
+
+        let mut counts = Grid::new(ys.len(), xs.len(), 0i32)?;
+
+        for claim in claims {
+            let corner = claim.far_corner();
+            let (x0, x1) = Self::bounds(&xs, claim.anchor.x, corner.x);
+            let (y0, y1) = Self::bounds(&ys, claim.anchor.y, corner.y);
+
+            let corners = [
+                (y0, x0, 1),
+                (y0, x1, -1),
+                (y1, x0, -1),
+                (y1, x1, 1),
+            ];
+
+            for (y, x, delta) in corners {
+                if let Some(cell) = counts.get_mut(y, x) {
+                    *cell += delta;
+                }
+            }
+        }
+
+        // Sum the differences into claim counts, row by row. The last row and
+        // column are the far edges, which have nothing beyond them.
+        let mut above = vec![0; xs.len()];
+
+        for y in 0 .. ys.len().saturating_sub(1) {
+            let mut left = 0;
+
+            for (x, total) in above.iter_mut().enumerate().take(xs.len() - 1) {
+                left += counts.get(y, x).copied().unwrap_or(0);
+                *total += left;
+                counts.set(y, x, *total);
+            }
+        }
+
+        Ok(Self { xs, ys, counts })
+    }
+
+    // Positions of a claim's start and end edges in the compressed
+    // coordinates
+    fn bounds(edges: &[i64], start: i64, end: i64) -> (usize, usize) {
+        let index = |edge| edges.binary_search(&edge).unwrap();
+
+        (index(start), index(end))
     }
 
-    claims
+    // Claims covering each cell as (x, y, count), skipping the far edges
+    fn cells(&self) -> impl Iterator<Item = (usize, usize, i32)> + '_ {
+        let rows = self.ys.len().saturating_sub(1);
+        let cols = self.xs.len().saturating_sub(1);
+
+        self.counts.iter()
+            .filter(move |(y, x, _)| *y < rows && *x < cols)
+            .map(|(y, x, count)| (x, y, *count))
+    }
+
+    // Square inches covered by two or more claims. Fails if the total
+    // doesn't fit in an i64.
+    fn overlapping(&self) -> Result<i64, Box<dyn Error>> {
+        self.cells()
+            .filter(|(_, _, count)| *count > 1)
+            .try_fold(0i64, |overlapping, (x, y, _)| {
+                let width = self.xs[x + 1] - self.xs[x];
+                let height = self.ys[y + 1] - self.ys[y];
+
+                width.checked_mul(height)
+                    .and_then(|area| overlapping.checked_add(area))
+            })
+            .ok_or_else(|| "Overlapping area is too large".into())
+    }
+
+    // Claims whose cells are all covered by that claim alone
+    fn intact<'a>(&self, claims: &'a [Claim]) -> Vec<&'a Claim> {
+        claims.iter()
+            .filter(|claim| {
+                let corner = claim.far_corner();
+                let (x0, x1) = Self::bounds(&self.xs, claim.anchor.x, corner.x);
+                let (y0, y1) = Self::bounds(&self.ys, claim.anchor.y, corner.y);
+
+                (y0 .. y1).all(|y| {
+                    (x0 .. x1).all(|x| self.counts.get(y, x) == Some(&1))
+                })
+            })
+            .collect()
+    }
 }
 
-fn main() {
-    // Collect command line args
-    let args: Vec<String> = env::args().collect();
+// Average time taken for an engine to count the overlap
+fn time(
+    claims: &[Claim],
+    engine: Engine,
+) -> Result<Duration, Box<dyn Error>> {
+    let start = Instant::now();
+
+    for _ in 0 .. BENCH_ITERATIONS {
+        std::hint::black_box(
+            engine.overlapping(std::hint::black_box(claims))?
+        );
+    }
+
+    Ok(start.elapsed() / BENCH_ITERATIONS)
+}
 
-    // If we got an arg, use it as the input, otherwise use stdin
-    let input: Box<io::Read> = if args.len() > 1 {
+fn bench(claims: &[Claim]) -> Result<(), Box<dyn Error>> {
+    println!("Dense:      {:?}", time(claims, Engine::Dense)?);
+    println!("Compressed: {:?}", time(claims, Engine::Compressed)?);
+
+    Ok(())
+}
+
+// Claims that don't overlap any other claim
+fn intact_claims(claims: &[Claim]) -> Vec<&Claim> {
+    claims.iter()
+        .filter(|claim| {
+            claims.iter()
+                .all(|other| other.id == claim.id || !claim.overlap(other))
+        })
+        .collect()
+}
+
+fn process_input(mut inp: impl io::Read) -> Result<Vec<Claim>, Box<dyn Error>> {
+    // Read the input into a buffer
+    let mut buffer = String::new();
+    inp.read_to_string(&mut buffer)?;
+
+    // Storage for processed claims.
+    let mut claims: Vec<Claim> = Vec::new();
+
+    for (count, line) in buffer.lines().enumerate() {
+        let claim = line.parse()
+            .map_err(|e| format!("Line {}: {}", count + 1, e))?;
+
+        claims.push(claim);
+    }
+
+    Ok(claims)
+}
+
+// Get an input reader
+fn input_reader(
+    args: Args,
+) -> Result<BufReader<Box<dyn io::Read>>, Box<dyn Error>> {
+    // Either read from the given file or stdin
+    let input: Box<dyn io::Read> = if args.len() > 1 {
         let filename = &args[1];
-        let fh = File::open(filename).unwrap();
+        let fh = File::open(filename)?;
         Box::new(fh)
     }
     else {
@@ -97,7 +593,231 @@ fn main() {
         Box::new(stdin)
     };
 
-    let claims = process_input(input);
+    let reader = BufReader::new(input);
+
+    Ok(reader)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let mut engine = Engine::Dense;
+    let mut run_bench = false;
+
+    // Scale the claims up, simulating a larger fabric
+    let mut scale = 1;
+
+    // File to write a heatmap of the fabric to
+    let mut heatmap = None;
+
+    for flag in &flags {
+        if flag == "--bench" {
+            run_bench = true;
+        }
+        else if let Some(e) = flag.strip_prefix("--engine=") {
+            engine = Engine::try_from(e)?;
+        }
+        else if let Some(n) = flag.strip_prefix("--scale=") {
+            scale = n.parse()?;
+        }
+        else if let Some(filename) = flag.strip_prefix("--heatmap=") {
+            heatmap = Some(filename.to_string());
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
-    println!("{:?}", claims);
+    let claims = process_input(input_reader(args)?)?
+        .iter()
+        .map(|c| c.scaled(scale))
+        .collect::<Result<Vec<Claim>, _>>()?;
+
+    if run_bench {
+        bench(&claims)?;
+        return Ok(());
+    }
+
+    let claimed = claims.iter()
+        .try_fold(0i64, |claimed, c| claimed.checked_add(c.rectangle.area()))
+        .ok_or("Claimed area is too large")?;
+
+    println!("Claimed: {}", claimed);
+    println!("Overlapping: {}", engine.overlapping(&claims)?);
+
+    let intact = engine.intact(&claims)?;
+
+    for claim in &intact {
+        println!("Intact: {}", claim.id);
+    }
+
+    if let Some(filename) = heatmap {
+        let mut fh = File::create(filename)?;
+        fh.write_all(&Fabric::new(&claims)?.heatmap(&intact)?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Taken from example on day 3
+    const EXAMPLE: &str = "#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2\n";
+
+    #[test]
+    fn test_intersection() {
+        let claims = process_input(EXAMPLE.as_bytes()).unwrap();
+
+        let expected = (Anchor { x: 3, y: 3 }, Rectangle {
+            width: 2,
+            height: 2,
+        });
+
+        assert_eq!(claims[0].intersection(&claims[1]), Some(expected));
+        assert_eq!(claims[0].overlap_area(&claims[1]), 4);
+        assert!(claims[1].overlap(&claims[0]));
+        assert!(!claims[0].overlap(&claims[2]));
+        assert!(!claims[1].overlap(&claims[2]));
+        assert_eq!(claims[2].rectangle.area(), 4);
+
+        // Rectangles sharing an edge don't overlap
+        let square = Rectangle { width: 2, height: 2 };
+        let left = Anchor { x: 0, y: 0 };
+        let right = Anchor { x: 2, y: 0 };
+
+        assert_eq!(square.intersection(&left, &square, &right), None);
+    }
+
+    #[test]
+    fn test_fabric() {
+        let claims = process_input(EXAMPLE.as_bytes()).unwrap();
+        let fabric = Fabric::new(&claims).unwrap();
+        let intact: Vec<i64> = intact_claims(&claims)
+            .iter()
+            .map(|c| c.id)
+            .collect();
+
+        assert_eq!(fabric.overlapping(), 4);
+        assert_eq!(intact, vec![3]);
+    }
+
+    #[test]
+    fn test_engines() {
+        let claims = process_input(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(Engine::Dense.overlapping(&claims).unwrap(), 4);
+        assert_eq!(Engine::Compressed.overlapping(&claims).unwrap(), 4);
+        assert_eq!(Engine::Compressed.overlapping(&[]).unwrap(), 0);
+
+        let scaled: Vec<Claim> = claims.iter()
+            .map(|c| c.scaled(3).unwrap())
+            .collect();
+
+        assert_eq!(Engine::Dense.overlapping(&scaled).unwrap(), 36);
+        assert_eq!(Engine::Compressed.overlapping(&scaled).unwrap(), 36);
+
+        for engine in [Engine::Dense, Engine::Compressed] {
+            let intact: Vec<i64> = engine.intact(&scaled)
+                .unwrap()
+                .iter()
+                .map(|c| c.id)
+                .collect();
+
+            assert_eq!(intact, vec![3]);
+        }
+
+        // Too large to hold densely, but compresses fine
+        let huge: Vec<Claim> = claims.iter()
+            .map(|c| c.scaled(10_000_000).unwrap())
+            .collect();
+
+        assert!(Engine::Dense.overlapping(&huge).is_err());
+        assert_eq!(
+            Engine::Compressed.overlapping(&huge).unwrap(),
+            400_000_000_000_000,
+        );
+
+        // Each overlap fits in an i64, but their total doesn't
+        let overflowing = process_input(concat!(
+            "#1 @ 0,0: 3037000499x3037000499\n",
+            "#2 @ 0,0: 3037000499x3037000499\n",
+            "#3 @ 3037000499,0: 3037000499x3037000499\n",
+            "#4 @ 3037000499,0: 3037000499x3037000499\n",
+        ).as_bytes()).unwrap();
+
+        assert!(Engine::Compressed.overlapping(&overflowing).is_err());
+
+        assert!(claims[0].scaled(0).is_err());
+        assert!(claims[0].scaled(-1).is_err());
+        assert!(claims[0].scaled(i64::MAX).is_err());
+        assert!(claims[0].scaled(i64::MAX / 4).is_err());
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let claims = process_input(EXAMPLE.as_bytes()).unwrap();
+        let fabric = Fabric::new(&claims).unwrap();
+        let image = fabric.heatmap(&intact_claims(&claims)).unwrap();
+
+        let header = b"P6\n7 7\n255\n";
+        assert!(image.starts_with(header));
+        assert_eq!(image.len(), header.len() + (7 * 7 * 3));
+
+        let pixel = |x: usize, y: usize| {
+            let start = header.len() + (((y * 7) + x) * 3);
+            &image[start .. start + 3]
+        };
+
+        assert_eq!(pixel(0, 0), EMPTY_COLOUR);
+        assert_eq!(pixel(1, 3), SINGLE_COLOUR);
+        assert_eq!(pixel(3, 3), [255, 255, 0]);
+        assert_eq!(pixel(5, 5), OUTLINE_COLOUR);
+        assert_eq!(pixel(6, 6), OUTLINE_COLOUR);
+    }
+
+    #[test]
+    fn test_claim_from_str() {
+        let claim: Claim = "#123 @ 3,2: 5x4".parse().unwrap();
+        let large: Claim = "#9876543210 @ 3,2: 5x4".parse().unwrap();
+
+        assert_eq!(large.id, 9_876_543_210);
+
+        assert_eq!(claim, Claim {
+            id: 123,
+            anchor: Anchor { x: 3, y: 2 },
+            rectangle: Rectangle { width: 5, height: 4 },
+        });
+
+        let tests = vec![
+            ("123 @ 3,2: 5x4", ClaimError::Id("123".into())),
+            ("#x @ 3,2: 5x4", ClaimError::Id("#x".into())),
+            ("#-1 @ 3,2: 5x4", ClaimError::Id("#-1".into())),
+            ("#+1 @ 3,2: 5x4", ClaimError::Id("#+1".into())),
+            ("#1 at 3,2: 5x4", ClaimError::At("at".into())),
+            ("#1 @ 3,2 5x4", ClaimError::Colon("3,2".into())),
+            ("#1 @ 3;2: 5x4", ClaimError::Anchor("3;2:".into())),
+            ("#1 @ 3,2: 5*4", ClaimError::Size("5*4".into())),
+            ("#1 @ 3,2: -5x4", ClaimError::Size("-5x4".into())),
+            ("#1 @ 3,2: +5x4", ClaimError::Size("+5x4".into())),
+            ("#1 @ 3,2:: 5x4", ClaimError::Anchor("3,2::".into())),
+            ("#1 @ 3,2:", ClaimError::Size("".into())),
+            ("#1 @ 3,2: 5x4 x", ClaimError::Trailing("x".into())),
+        ];
+
+        for (input, err) in tests {
+            assert_eq!(input.parse::<Claim>(), Err(err));
+        }
+
+        let err = process_input("#1 @ 1,3: 4x4\n#2 @ 3,1 4x4\n".as_bytes())
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Line 2: Expected ':' after anchor: '3,1'",
+        );
+    }
 }
@@ -1,6 +1,8 @@
 // day
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{
     self,
@@ -8,31 +10,292 @@ use std::io::{
     BufReader,
 };
 
-fn fuel_for_fuel(mass: i64) -> i64 {
-    let mut additional_fuel = 0;
-    let mut next_mass = mass;
+// How much fuel a given mass needs: mass / divisor - subtract, never going
+// below zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FuelModel {
+    divisor:  u64,
+    subtract: u64,
+}
+
+impl Default for FuelModel {
+    fn default() -> Self {
+        Self {
+            divisor:  3,
+            subtract: 2,
+        }
+    }
+}
+
+impl FuelModel {
+    fn new(divisor: u64, subtract: u64) -> Result<Self, Box<dyn Error>> {
+        if divisor == 0 {
+            return Err("Fuel model divisor must not be 0".into());
+        }
+
+        // Fuel for fuel would never reach zero
+        if divisor == 1 && subtract == 0 {
+            return Err("Fuel model must reduce the mass".into());
+        }
+
+        Ok(Self {
+            divisor,
+            subtract,
+        })
+    }
+
+    fn fuel_required(&self, mass: u64) -> u64 {
+        (mass / self.divisor).saturating_sub(self.subtract)
+    }
+
+    // The fuel needed to carry the fuel, then the fuel to carry that, until
+    // no more fuel is needed.
+    fn fuel_chain(&self, fuel: u64) -> Vec<u64> {
+        let mut chain = Vec::new();
+        let mut next_mass = self.fuel_required(fuel);
 
-    while next_mass > 0 {
-        let required = fuel_required(next_mass);
+        while next_mass > 0 {
+            chain.push(next_mass);
+            next_mass = self.fuel_required(next_mass);
+        }
+
+        chain
+    }
+}
+
+// Fuel required for a single module
+#[derive(Debug, PartialEq)]
+struct ModuleFuel {
+    // Line number the module was read from
+    line:          usize,
+    mass:          u64,
+    fuel:          u64,
+    fuel_for_fuel: u64,
+
+    // Each step of the fuel for fuel calculation
+    chain:         Vec<u64>,
+}
 
-        if required < 0 {
-            break;
+impl ModuleFuel {
+    fn new(model: &FuelModel, line: usize, mass: u64) -> Self {
+        let fuel = model.fuel_required(mass);
+        let chain = model.fuel_chain(fuel);
+        let fuel_for_fuel = chain.iter()
+            .fold(0, |acc: u64, f| acc.saturating_add(*f));
+
+        Self {
+            line,
+            mass,
+            fuel,
+            fuel_for_fuel,
+            chain,
         }
+    }
 
-        additional_fuel = additional_fuel + required;
-        next_mass = required as i64;
+    // The chain joined with the given separator
+    fn chain_string(&self, separator: &str) -> String {
+        let chain: Vec<String> = self.chain.iter()
+            .map(|f| f.to_string())
+            .collect();
+
+        chain.join(separator)
     }
+}
 
-    additional_fuel
+// Ways the fuel report can be output
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Table,
+    Csv,
+    Json,
 }
 
-fn fuel_required(mass: i64) -> i64 {
-    let fuel = (mass as f64 / 3.0).floor() as i64 - 2;
-    fuel
+impl TryFrom<&str> for OutputFormat {
+    type Error = Box<dyn Error>;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        match input {
+            "text"  => Ok(Self::Text),
+            "table" => Ok(Self::Table),
+            "csv"   => Ok(Self::Csv),
+            "json"  => Ok(Self::Json),
+            _       => Err(format!("Unknown format: {}", input).into()),
+        }
+    }
+}
+
+// Fuel required for every module
+#[derive(Debug, Default)]
+struct FuelReport {
+    modules: Vec<ModuleFuel>,
+}
+
+impl FuelReport {
+    // Fuel required for the base mass of the modules
+    fn mass_fuel(&self) -> u64 {
+        self.modules.iter()
+            .fold(0, |acc, m| acc.saturating_add(m.fuel))
+    }
+
+    // Fuel required to carry the fuel
+    fn fuel_fuel(&self) -> u64 {
+        self.modules.iter()
+            .fold(0, |acc, m| acc.saturating_add(m.fuel_for_fuel))
+    }
+
+    fn total(&self) -> u64 {
+        self.mass_fuel().saturating_add(self.fuel_fuel())
+    }
+
+    fn format(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text  => format!("{}\n", self),
+            OutputFormat::Table => self.to_table(),
+            OutputFormat::Csv   => self.to_csv(),
+            OutputFormat::Json  => self.to_json(),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut table = format!(
+            "{:>6}  {:>10}  {:>10}  {:>10}  {}\n",
+            "Line",
+            "Mass",
+            "Fuel",
+            "Fuel fuel",
+            "Chain",
+        );
+
+        for module in &self.modules {
+            table.push_str(&format!(
+                "{:>6}  {:>10}  {:>10}  {:>10}  {}\n",
+                module.line,
+                module.mass,
+                module.fuel,
+                module.fuel_for_fuel,
+                module.chain_string(" -> "),
+            ));
+        }
+
+        table.push_str(&format!(
+            "{:>6}  {:>10}  {:>10}  {:>10}  {}\n",
+            "Total",
+            "",
+            self.mass_fuel(),
+            self.fuel_fuel(),
+            self.total(),
+        ));
+
+        table
+    }
+
+    // The chain is separated by ; so it stays in one column.
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("line,mass,fuel,fuel_for_fuel,chain\n");
+
+        for module in &self.modules {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                module.line,
+                module.mass,
+                module.fuel,
+                module.fuel_for_fuel,
+                module.chain_string(";"),
+            ));
+        }
+
+        csv
+    }
+
+    fn to_json(&self) -> String {
+        let modules: Vec<String> = self.modules.iter()
+            .map(|module| {
+                format!(
+                    concat!(
+                        r#"{{"line":{},"mass":{},"fuel":{},"#,
+                        r#""fuel_for_fuel":{},"chain":[{}]}}"#,
+                    ),
+                    module.line,
+                    module.mass,
+                    module.fuel,
+                    module.fuel_for_fuel,
+                    module.chain_string(","),
+                )
+            })
+            .collect();
+
+        format!(
+            r#"{{"modules":[{}],"mass_fuel":{},"fuel_fuel":{},"total":{}}}"#,
+            modules.join(","),
+            self.mass_fuel(),
+            self.fuel_fuel(),
+            self.total(),
+        ) + "\n"
+    }
+}
+
+impl fmt::Display for FuelReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for module in &self.modules {
+            writeln!(f, "{line}: {mass} -> {fuel} -> {fuel_fuel}",
+                line      = module.line,
+                mass      = module.mass,
+                fuel      = module.fuel,
+                fuel_fuel = module.fuel_for_fuel,
+            )?;
+        }
+
+        writeln!(f, "Fuel required for base mass: {}", self.mass_fuel())?;
+        writeln!(f, "Fuel required for fuel: {}", self.fuel_fuel())?;
+        write!(f, "Fuel required total: {}", self.total())
+    }
+}
+
+// Build a fuel report from module masses, one per line
+fn fuel_report(
+    reader: impl BufRead,
+    model: &FuelModel,
+) -> Result<FuelReport, Box<dyn Error>> {
+    let mut report: FuelReport = Default::default();
+
+    for (count, line) in reader.lines().enumerate() {
+        let line = line?;
+        let mass: u64 = line.trim().parse()
+            .map_err(|e| format!("Line {}: {}: {}", count + 1, line, e))?;
+
+        report.modules.push(ModuleFuel::new(model, count + 1, mass));
+    }
+
+    Ok(report)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Vec<String>, Vec<String>) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let default = FuelModel::default();
+    let mut divisor = default.divisor;
+    let mut subtract = default.subtract;
+    let mut format = OutputFormat::Text;
+
+    for flag in &flags {
+        if let Some(n) = flag.strip_prefix("--divisor=") {
+            divisor = n.parse()?;
+        }
+        else if let Some(n) = flag.strip_prefix("--subtract=") {
+            subtract = n.parse()?;
+        }
+        else if let Some(f) = flag.strip_prefix("--format=") {
+            format = OutputFormat::try_from(f)?;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
+
+    let model = FuelModel::new(divisor, subtract)?;
 
     // Either read from the given file or stdin
     let input: Box<dyn io::Read> = if args.len() > 1 {
@@ -46,28 +309,110 @@ fn main() -> Result<(), Box<dyn Error>> {
     };
 
     let reader = BufReader::new(input);
-    let mut mass_fuel_required = 0;
-    let mut fuel_fuel_required = 0;
+    let report = fuel_report(reader, &model)?;
 
-    for (count, line) in reader.lines().enumerate() {
-        let mass: i64 = line?.parse()?;
-        let fuel = fuel_required(mass);
-        let fuel_fuel = fuel_for_fuel(fuel as i64);
-
-        println!("{line}: {mass} -> {fuel} -> {fuel_fuel}",
-            line      = count + 1,
-            mass      = mass,
-            fuel      = fuel,
-            fuel_fuel = fuel_fuel,
-        );
+    print!("{}", report.format(format));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuel_required() {
+        // Taken from example on day 1
+        let tests = vec![
+            (12, 2),
+            (14, 2),
+            (1969, 654),
+            (100756, 33583),
+            (0, 0),
+            (5, 0),
+        ];
 
-        mass_fuel_required = mass_fuel_required + fuel;
-        fuel_fuel_required = fuel_fuel_required + fuel_fuel;
+        let model = FuelModel::default();
+
+        for (mass, fuel) in tests {
+            assert_eq!(model.fuel_required(mass), fuel);
+        }
     }
 
-    println!("Fuel required for base mass: {}", mass_fuel_required);
-    println!("Fuel required for fuel: {}", fuel_fuel_required);
-    println!("Fuel required total: {}", mass_fuel_required + fuel_fuel_required);
+    #[test]
+    fn test_fuel_for_fuel() {
+        // Taken from example on day 1, part 2
+        let tests = vec![
+            (14, 2),
+            (1969, 966),
+            (100756, 50346),
+        ];
 
-    Ok(())
+        let model = FuelModel::default();
+
+        for (mass, total) in tests {
+            let module = ModuleFuel::new(&model, 1, mass);
+
+            assert_eq!(module.fuel + module.fuel_for_fuel, total);
+        }
+    }
+
+    #[test]
+    fn test_fuel_model() {
+        let model = FuelModel::new(2, 0).unwrap();
+
+        assert_eq!(model.fuel_required(10), 5);
+        assert_eq!(model.fuel_chain(5), vec![2, 1]);
+
+        assert!(FuelModel::new(0, 2).is_err());
+        assert!(FuelModel::new(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_fuel_report() {
+        let input = "12\n14\n1969\n";
+        let report = fuel_report(input.as_bytes(), &FuelModel::default())
+            .unwrap();
+
+        assert_eq!(report.mass_fuel(), 658);
+        assert_eq!(report.fuel_fuel(), 312);
+        assert_eq!(report.total(), 970);
+
+        let err = fuel_report("12\nabc\n".as_bytes(), &FuelModel::default());
+
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_fuel_report_formats() {
+        let report = fuel_report("14\n1969\n".as_bytes(), &FuelModel::default())
+            .unwrap();
+
+        assert_eq!(report.modules[1].chain, vec![216, 70, 21, 5]);
+
+        let expected = concat!(
+            "line,mass,fuel,fuel_for_fuel,chain\n",
+            "1,14,2,0,\n",
+            "2,1969,654,312,216;70;21;5\n",
+        );
+
+        assert_eq!(report.format(OutputFormat::Csv), expected);
+
+        let expected = concat!(
+            r#"{"modules":["#,
+            r#"{"line":1,"mass":14,"fuel":2,"fuel_for_fuel":0,"chain":[]},"#,
+            r#"{"line":2,"mass":1969,"fuel":654,"fuel_for_fuel":312,"#,
+            r#""chain":[216,70,21,5]}"#,
+            r#"],"mass_fuel":656,"fuel_fuel":312,"total":968}"#,
+            "\n",
+        );
+
+        assert_eq!(report.format(OutputFormat::Json), expected);
+
+        let table = report.format(OutputFormat::Table);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].ends_with("654         312  216 -> 70 -> 21 -> 5"));
+    }
 }
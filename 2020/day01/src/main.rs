@@ -1,4 +1,5 @@
 // day
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -7,6 +8,16 @@ use std::io::{
     prelude::*,
     BufReader,
 };
+use std::time::{
+    Duration,
+    Instant,
+};
+
+// The sum we're looking for in the expense report
+const TARGET: u64 = 2020;
+
+// Iterations to run each solver for when benchmarking
+const BENCH_ITERATIONS: u32 = 100;
 
 // CLI arguments
 type Args = Vec<String>;
@@ -37,9 +48,197 @@ fn input_to_vec(input: &str) -> Vec<u64> {
         .collect()
 }
 
+// Find k entries in the sorted input that sum to target, returning them in
+// ascending order.
+fn k_sum_sorted(sorted: &[u64], k: usize, target: u64) -> Option<Vec<u64>> {
+    match k {
+        0 => {
+            if target == 0 { Some(Vec::new()) } else { None }
+        },
+        1 => {
+            sorted.binary_search(&target)
+                .ok()
+                .map(|_| vec![target])
+        },
+        2 => {
+            // Two pointers, walking in from either end
+            if sorted.len() < 2 {
+                return None;
+            }
+
+            let mut left = 0;
+            let mut right = sorted.len() - 1;
+
+            while left < right {
+                // A sum too large for a u64 is too big for any target
+                match sorted[left].checked_add(sorted[right]) {
+                    Some(sum) if sum == target => {
+                        return Some(vec![sorted[left], sorted[right]]);
+                    },
+                    Some(sum) if sum < target => left += 1,
+                    _                         => right -= 1,
+                }
+            }
+
+            None
+        },
+        _ => {
+            // Fix the smallest entry and look for k - 1 entries after it.
+            for (i, value) in sorted.iter().enumerate() {
+                // Every following entry is at least this big, so there's no
+                // point continuing once we'd overshoot.
+                if value.saturating_mul(k as u64) > target {
+                    break;
+                }
+
+                // We've already tried this value
+                if i > 0 && sorted[i - 1] == *value {
+                    continue;
+                }
+
+                let rest = &sorted[i + 1 ..];
+                let found = k_sum_sorted(rest, k - 1, target - value);
+
+                if let Some(mut found) = found {
+                    found.insert(0, *value);
+                    return Some(found);
+                }
+            }
+
+            None
+        },
+    }
+}
+
+// Find k entries in the expense report that sum to target
+fn find_k_sum(values: &[u64], k: usize, target: u64) -> Option<Vec<u64>> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    k_sum_sorted(&sorted, k, target)
+}
+
+// Distinct values in the expense report, with how many times each appears,
+// in ascending order.
+fn value_counts(values: &[u64]) -> Vec<(u64, usize)> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let mut counts: Vec<(u64, usize)> = Vec::new();
+
+    for value in sorted {
+        match counts.last_mut() {
+            Some((last, count)) if *last == value => *count += 1,
+            _                                     => counts.push((value, 1)),
+        }
+    }
+
+    counts
+}
+
+// Walk every combination of k values summing to target, calling f with each.
+// A value may be used as many times as it appears in the input, and each
+// combination of values is only visited once.
+fn walk_k_sums<F>(
+    counts: &[(u64, usize)],
+    k: usize,
+    target: u64,
+    chosen: &mut Vec<u64>,
+    f: &mut F,
+)
+where
+    F: FnMut(&[u64]),
+{
+    if k == 0 {
+        if target == 0 {
+            f(chosen);
+        }
+
+        return;
+    }
+
+    let (value, count) = match counts.first() {
+        Some(first) => *first,
+        None        => return,
+    };
+
+    // Every remaining value is at least this big, so we'd overshoot.
+    if value.saturating_mul(k as u64) > target {
+        return;
+    }
+
+    // Take this value between 0 and count times, then move on to the next.
+    for take in 0 ..= count.min(k) {
+        let used = value * take as u64;
+
+        if used > target {
+            break;
+        }
+
+        chosen.extend(std::iter::repeat_n(value, take));
+        walk_k_sums(&counts[1 ..], k - take, target - used, chosen, f);
+        chosen.truncate(chosen.len() - take);
+    }
+}
+
+// Every combination of k entries summing to target, each in ascending order.
+fn all_k_sums(values: &[u64], k: usize, target: u64) -> Vec<Vec<u64>> {
+    let mut all = Vec::new();
+
+    walk_k_sums(&value_counts(values), k, target, &mut Vec::new(), &mut |c| {
+        all.push(c.to_vec())
+    });
+
+    all.sort();
+    all
+}
+
+// Number of combinations of k entries summing to target
+fn count_k_sums(values: &[u64], k: usize, target: u64) -> usize {
+    let mut count = 0;
+
+    walk_k_sums(&value_counts(values), k, target, &mut Vec::new(), &mut |_| {
+        count += 1
+    });
+
+    count
+}
+
+// How many solutions to look for
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    First,
+    All,
+    Count,
+}
+
+impl TryFrom<&str> for Mode {
+    type Error = Box<dyn Error>;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        match input {
+            "first" => Ok(Self::First),
+            "all"   => Ok(Self::All),
+            "count" => Ok(Self::Count),
+            _       => Err(format!("Unknown mode: {}", input).into()),
+        }
+    }
+}
+
 // In this list, the two entries that sum to 2020 are 1721 and 299. Multiplying
 // them together produces 1721 * 299 = 514579, so the correct answer is 514579.
-fn part_one(input: &[u64]) -> u64 {
+fn part_one(input: &[u64]) -> Option<u64> {
+    find_k_sum(input, 2, TARGET)
+        .map(|entries| entries.iter().product())
+}
+
+fn part_two(input: &[u64]) -> Option<u64> {
+    find_k_sum(input, 3, TARGET)
+        .map(|entries| entries.iter().product())
+}
+
+// The original nested loop solutions, kept for benchmarking.
+fn part_one_loops(input: &[u64]) -> u64 {
     for (i, left) in input.iter().enumerate() {
         // Take entry and multiply it against every other number.
         for (j, right) in input.iter().enumerate() {
@@ -50,7 +249,7 @@ fn part_one(input: &[u64]) -> u64 {
 
             let sum = left + right;
 
-            if sum == 2020 {
+            if sum == TARGET {
                 return left * right;
             }
         }
@@ -60,7 +259,7 @@ fn part_one(input: &[u64]) -> u64 {
 }
 
 // Please, my code, she is dying.
-fn part_two(input: &[u64]) -> u64 {
+fn part_two_loops(input: &[u64]) -> u64 {
     for (i, left) in input.iter().enumerate() {
         // Take entry and multiply it against every other number.
         for (j, middle) in input.iter().enumerate() {
@@ -76,7 +275,7 @@ fn part_two(input: &[u64]) -> u64 {
 
                 let sum = left + middle + right;
 
-                if sum == 2020 {
+                if sum == TARGET {
                     return left * middle * right;
                 }
             }
@@ -86,8 +285,50 @@ fn part_two(input: &[u64]) -> u64 {
     0
 }
 
+// Average time taken to run f over the input
+fn time<F, T>(input: &[u64], f: F) -> Duration
+where
+    F: Fn(&[u64]) -> T,
+{
+    let start = Instant::now();
+
+    for _ in 0 .. BENCH_ITERATIONS {
+        std::hint::black_box(f(std::hint::black_box(input)));
+    }
+
+    start.elapsed() / BENCH_ITERATIONS
+}
+
+fn bench(input: &[u64]) {
+    println!("Part 1 loops:   {:?}", time(input, part_one_loops));
+    println!("Part 1 k-sum:   {:?}", time(input, part_one));
+    println!("Part 2 loops:   {:?}", time(input, part_two_loops));
+    println!("Part 2 k-sum:   {:?}", time(input, part_two));
+}
+
+fn print_answer(part: usize, answer: Option<u64>) {
+    match answer {
+        Some(answer) => println!("Part {}: {}", part, answer),
+        None         => println!("Part {}: No entries sum to {}", part, TARGET),
+    }
+}
+
+// Print every combination of k entries summing to the target, with product
+fn print_all(part: usize, input: &[u64], k: usize) {
+    for entries in all_k_sums(input, k, TARGET) {
+        let product: u64 = entries.iter().product();
+        let entries: Vec<String> = entries.iter()
+            .map(|e| e.to_string())
+            .collect();
+
+        println!("Part {}: {} = {}", part, entries.join(" * "), product);
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
 
     // Get the input
     let mut buffer = String::new();
@@ -96,13 +337,35 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let input = input_to_vec(&buffer);
 
-    let ret = part_one(&input);
+    let mut mode = Mode::First;
 
-    println!("Part 1: {}", ret);
-
-    let ret = part_two(&input);
+    for flag in &flags {
+        if flag == "--bench" {
+            bench(&input);
+            return Ok(());
+        }
+        else if let Some(m) = flag.strip_prefix("--mode=") {
+            mode = Mode::try_from(m)?;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
-    println!("Part 2: {}", ret);
+    match mode {
+        Mode::First => {
+            print_answer(1, part_one(&input));
+            print_answer(2, part_two(&input));
+        },
+        Mode::All => {
+            print_all(1, &input, 2);
+            print_all(2, &input, 3);
+        },
+        Mode::Count => {
+            println!("Part 1: {}", count_k_sums(&input, 2, TARGET));
+            println!("Part 2: {}", count_k_sums(&input, 3, TARGET));
+        },
+    }
 
     Ok(())
 }
@@ -110,4 +373,73 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    // Taken from example on day 1
+    const EXAMPLE: &[u64] = &[1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(find_k_sum(EXAMPLE, 2, TARGET), Some(vec![299, 1721]));
+        assert_eq!(part_one(EXAMPLE), Some(514579));
+        assert_eq!(part_one(EXAMPLE), Some(part_one_loops(EXAMPLE)));
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(find_k_sum(EXAMPLE, 3, TARGET), Some(vec![366, 675, 979]));
+        assert_eq!(part_two(EXAMPLE), Some(241861950));
+        assert_eq!(part_two(EXAMPLE), Some(part_two_loops(EXAMPLE)));
+    }
+
+    #[test]
+    fn test_find_k_sum() {
+        let values = vec![5, 1, 4, 2, 3, 3];
+
+        assert_eq!(find_k_sum(&values, 0, 0), Some(vec![]));
+        assert_eq!(find_k_sum(&values, 1, 4), Some(vec![4]));
+        assert_eq!(find_k_sum(&values, 2, 6), Some(vec![1, 5]));
+        assert_eq!(find_k_sum(&values, 4, 9), Some(vec![1, 2, 3, 3]));
+        assert_eq!(find_k_sum(&values, 6, 18), Some(vec![1, 2, 3, 3, 4, 5]));
+        assert_eq!(find_k_sum(&values, 2, 10), None);
+        assert_eq!(find_k_sum(&values, 7, 18), None);
+        assert_eq!(find_k_sum(&[1010], 2, TARGET), None);
+        assert_eq!(find_k_sum(&[], 2, TARGET), None);
+        assert_eq!(find_k_sum(&[1, 5, u64::MAX], 2, 6), Some(vec![1, 5]));
+        assert_eq!(find_k_sum(&[u64::MAX, u64::MAX], 2, 6), None);
+    }
+
+    #[test]
+    fn test_all_k_sums() {
+        let values = vec![1, 2, 3, 4, 5];
+
+        let expected = vec![
+            vec![1, 5],
+            vec![2, 4],
+        ];
+
+        assert_eq!(all_k_sums(&values, 2, 6), expected);
+        assert_eq!(count_k_sums(&values, 2, 6), 2);
+        assert_eq!(count_k_sums(&values, 3, 9), 2);
+        assert_eq!(count_k_sums(&values, 2, 100), 0);
+        assert_eq!(all_k_sums(EXAMPLE, 3, TARGET), vec![vec![366, 675, 979]]);
+    }
+
+    #[test]
+    fn test_all_k_sums_duplicates() {
+        // A value can only be used as many times as it appears
+        assert_eq!(count_k_sums(&[1010], 2, TARGET), 0);
+        assert_eq!(all_k_sums(&[1010, 1010], 2, TARGET), vec![vec![1010; 2]]);
+
+        // Repeated values don't produce repeated combinations
+        let values = vec![1, 1, 2, 2, 3];
+
+        let expected = vec![
+            vec![1, 1, 3],
+            vec![1, 2, 2],
+        ];
+
+        assert_eq!(all_k_sums(&values, 3, 5), expected);
+        assert_eq!(count_k_sums(&values, 2, 3), 1);
+        assert_eq!(count_k_sums(&values, 2, 4), 2);
+    }
 }
@@ -1,58 +1,450 @@
 // day
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{
     self,
     prelude::*,
     BufReader,
 };
-use std::ops::RangeInclusive;
+
+// Policies applied to the password database when none is given.
+// $min, $max and $letter are taken from each line of the database.
+const PART_ONE_POLICY: &str = "count($letter, $min, $max)";
+const PART_TWO_POLICY: &str = "xor(at($min, $letter), at($max, $letter))";
 
 #[derive(Debug, PartialEq)]
 enum Compliance {
     Compliant,
     NonCompliant,
+
+    // Non-compliant because the policy couldn't be applied to the password,
+    // such as a position beyond the end of it.
+    Invalid(String),
+}
+
+// Problems parsing a line of the password database
+#[derive(Debug, PartialEq)]
+enum EntryError {
+    // No ':' separating the policy from the password
+    MissingPassword,
+
+    // No letter following the min-max range
+    MissingLetter,
+
+    // No '-' in the min-max range
+    BadRange(String),
+
+    BadNumber(String),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingPassword => write!(f, "Missing ':' before password"),
+            Self::MissingLetter   => write!(f, "Missing policy letter"),
+            Self::BadRange(range) => write!(f, "Invalid range '{}'", range),
+            Self::BadNumber(num)  => write!(f, "Invalid number '{}'", num),
+        }
+    }
+}
+
+impl Error for EntryError {}
+
+#[derive(Debug, PartialEq)]
+struct RuleError(String);
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid policy rule: {}", self.0)
+    }
+}
+
+impl Error for RuleError {}
+
+// Numbers in a rule, either given directly or taken from the database line
+#[derive(Clone, Debug, PartialEq)]
+enum Number {
+    Literal(usize),
+    Min,
+    Max,
+}
+
+// Text in a rule, either given directly or taken from the database line
+#[derive(Clone, Debug, PartialEq)]
+enum Text {
+    Literal(String),
+    Letter,
+}
+
+// A parsed password policy rule
+#[derive(Clone, Debug, PartialEq)]
+enum Rule {
+    // Text occurs between min and max times: count(text, min, max)
+    Count(Text, Number, Number),
+
+    // Text occurs at the 1-indexed position: at(pos, text)
+    At(Number, Text),
+
+    // Text occurs anywhere: contains(text)
+    Contains(Text),
+
+    // Text doesn't occur anywhere: forbid(text)
+    Forbid(Text),
+
+    Not(Box<Rule>),
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+
+    // Exactly one of the rules holds
+    Xor(Vec<Rule>),
 }
 
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(usize),
+    Text(String),
+    Var(String),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, RuleError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    // Take characters while they match the predicate
+    fn take_while<F>(
+        chars: &mut std::iter::Peekable<std::str::Chars>,
+        f: F,
+    ) -> String
+    where
+        F: Fn(char) -> bool,
+    {
+        let mut s = String::new();
+
+        while let Some(c) = chars.next_if(|c| f(*c)) {
+            s.push(c);
+        }
+
+        s
+    }
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' => {
+                chars.next();
+            },
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            },
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            },
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            },
+            '"' => {
+                chars.next();
+                let text = take_while(&mut chars, |c| c != '"');
+
+                if chars.next() != Some('"') {
+                    return Err(RuleError("Unterminated string".into()));
+                }
+
+                tokens.push(Token::Text(text));
+            },
+            '$' => {
+                chars.next();
+                let var = take_while(&mut chars, |c| c.is_ascii_alphabetic());
+                tokens.push(Token::Var(var));
+            },
+            '0' ..= '9' => {
+                let num = take_while(&mut chars, |c| c.is_ascii_digit());
+                let num = num.parse()
+                    .map_err(|_| RuleError(format!("Bad number {}", num)))?;
+
+                tokens.push(Token::Number(num));
+            },
+            c if c.is_alphabetic() => {
+                let ident = take_while(&mut chars, |c| c.is_alphanumeric());
+                tokens.push(Token::Ident(ident));
+            },
+            _ => {
+                return Err(RuleError(format!("Unexpected '{}'", c)));
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Arguments to a rule function, before we know which type is wanted
 #[derive(Debug)]
-struct PasswordPolicy {
-    min: usize,
-    max: usize,
-    letter: String,
+enum Arg {
+    Rule(Rule),
+    Number(Number),
+    Text(Text),
 }
 
-impl PasswordPolicy {
-    fn letter(&self) -> &str {
-        &self.letter
+impl Arg {
+    fn expected(self, kind: &str) -> RuleError {
+        RuleError(format!("Expected {}, got {:?}", kind, self))
     }
 
-    fn min_max(&self) -> RangeInclusive<usize> {
-        self.min ..= self.max
+    fn into_rule(self) -> Result<Rule, RuleError> {
+        match self {
+            Self::Rule(rule) => Ok(rule),
+            arg              => Err(arg.expected("rule")),
+        }
     }
 
-    // min and max are used as positions for part 2
-    fn pos_a(&self) -> usize {
-        self.min
+    fn into_number(self) -> Result<Number, RuleError> {
+        match self {
+            Self::Number(num) => Ok(num),
+            arg               => Err(arg.expected("number")),
+        }
     }
 
-    fn pos_b(&self) -> usize {
-        self.max
+    // Numbers are allowed as text, for passwords containing digits.
+    fn into_text(self) -> Result<Text, RuleError> {
+        match self {
+            Self::Text(text) => Ok(text),
+            Self::Number(Number::Literal(num)) => {
+                Ok(Text::Literal(num.to_string()))
+            },
+            arg => Err(arg.expected("text")),
+        }
     }
 }
 
-impl From<&str> for PasswordPolicy {
-    fn from(input: &str) -> Self {
-        let policy_parts: Vec<&str> = input.split(' ').collect();
-        let min_max: Vec<&str> = policy_parts[0].split('-').collect();
-        let min: usize = min_max[0].parse().expect("Couldn't parse min number");
-        let max: usize = min_max[1].parse().expect("Couldn't parse max number");
+struct Parser {
+    tokens: Vec<Token>,
+    pos:    usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
 
-        Self {
-            min: min,
-            max: max,
-            letter: policy_parts[1].into(),
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), RuleError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            token => {
+                let err = format!("Expected {:?}, got {:?}", expected, token);
+                Err(RuleError(err))
+            },
+        }
+    }
+
+    fn parse_arg(&mut self) -> Result<Arg, RuleError> {
+        let arg = match self.next() {
+            Some(Token::Ident(ident)) => {
+                // An identifier followed by '(' is a nested rule, otherwise
+                // it's bare text.
+                if self.peek() == Some(&Token::Open) {
+                    Arg::Rule(self.parse_call(&ident)?)
+                }
+                else {
+                    Arg::Text(Text::Literal(ident))
+                }
+            },
+            Some(Token::Number(num)) => Arg::Number(Number::Literal(num)),
+            Some(Token::Text(text))  => Arg::Text(Text::Literal(text)),
+            Some(Token::Var(var)) => {
+                match var.as_str() {
+                    "min"    => Arg::Number(Number::Min),
+                    "max"    => Arg::Number(Number::Max),
+                    "letter" => Arg::Text(Text::Letter),
+                    _        => {
+                        let err = format!("Unknown variable ${}", var);
+                        return Err(RuleError(err));
+                    },
+                }
+            },
+            token => {
+                return Err(RuleError(format!("Unexpected {:?}", token)));
+            },
+        };
+
+        Ok(arg)
+    }
+
+    // Parse the arguments of a rule function and build the rule
+    fn parse_call(&mut self, name: &str) -> Result<Rule, RuleError> {
+        self.expect(Token::Open)?;
+
+        let mut args = Vec::new();
+
+        if self.peek() != Some(&Token::Close) {
+            loop {
+                args.push(self.parse_arg()?);
+
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+
+                self.next();
+            }
         }
+
+        self.expect(Token::Close)?;
+
+        Rule::from_call(name, args)
+    }
+
+    fn parse(mut self) -> Result<Rule, RuleError> {
+        let rule = match self.next() {
+            Some(Token::Ident(name)) => self.parse_call(&name)?,
+            token => {
+                let err = format!("Expected rule, got {:?}", token);
+                return Err(RuleError(err));
+            },
+        };
+
+        match self.peek() {
+            None        => Ok(rule),
+            Some(token) => Err(RuleError(format!("Trailing {:?}", token))),
+        }
+    }
+}
+
+impl Rule {
+    fn from_call(name: &str, args: Vec<Arg>) -> Result<Self, RuleError> {
+        let arity = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            }
+            else {
+                Err(RuleError(format!(
+                    "{} takes {} arguments, got {}",
+                    name,
+                    n,
+                    args.len(),
+                )))
+            }
+        };
+
+        let rules = |args: Vec<Arg>| -> Result<Vec<Rule>, RuleError> {
+            if args.is_empty() {
+                let err = format!("{} needs at least one rule", name);
+                return Err(RuleError(err));
+            }
+
+            args.into_iter()
+                .map(|arg| arg.into_rule())
+                .collect()
+        };
+
+        let rule = match name {
+            "count" => {
+                arity(3)?;
+                let mut args = args.into_iter();
+
+                Self::Count(
+                    args.next().unwrap().into_text()?,
+                    args.next().unwrap().into_number()?,
+                    args.next().unwrap().into_number()?,
+                )
+            },
+            "at" => {
+                arity(2)?;
+                let mut args = args.into_iter();
+
+                Self::At(
+                    args.next().unwrap().into_number()?,
+                    args.next().unwrap().into_text()?,
+                )
+            },
+            "contains" => {
+                arity(1)?;
+                Self::Contains(args.into_iter().next().unwrap().into_text()?)
+            },
+            "forbid" => {
+                arity(1)?;
+                Self::Forbid(args.into_iter().next().unwrap().into_text()?)
+            },
+            "not" => {
+                arity(1)?;
+                let rule = args.into_iter().next().unwrap().into_rule()?;
+                Self::Not(Box::new(rule))
+            },
+            "and" => Self::And(rules(args)?),
+            "or"  => Self::Or(rules(args)?),
+            "xor" => Self::Xor(rules(args)?),
+            _     => return Err(RuleError(format!("Unknown rule {}", name))),
+        };
+
+        Ok(rule)
+    }
+}
+
+impl TryFrom<&str> for Rule {
+    type Error = RuleError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let parser = Parser {
+            tokens: tokenize(input)?,
+            pos:    0,
+        };
+
+        parser.parse()
+    }
+}
+
+#[derive(Debug)]
+struct PasswordPolicy {
+    min:    usize,
+    max:    usize,
+    letter: String,
+}
+
+impl TryFrom<&str> for PasswordPolicy {
+    type Error = EntryError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (min_max, letter) = input.trim().split_once(' ')
+            .ok_or(EntryError::MissingLetter)?;
+
+        let (min, max) = min_max.split_once('-')
+            .ok_or_else(|| EntryError::BadRange(min_max.into()))?;
+
+        let number = |num: &str| {
+            num.parse()
+                .map_err(|_| EntryError::BadNumber(num.into()))
+        };
+
+        let letter = letter.trim();
+
+        if letter.is_empty() {
+            return Err(EntryError::MissingLetter);
+        }
+
+        Ok(Self {
+            min:    number(min)?,
+            max:    number(max)?,
+            letter: letter.into(),
+        })
+    }
+}
+
+impl fmt::Display for PasswordPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{} {}", self.min, self.max, self.letter)
     }
 }
 
@@ -62,58 +454,266 @@ struct PasswordEntry {
     password: String,
 }
 
-impl From<&str> for PasswordEntry {
-    fn from(input: &str) -> Self {
-        let v: Vec<&str> = input.split(':').collect();
-        let policy = v[0];
-        let password = v[1];
+impl TryFrom<&str> for PasswordEntry {
+    type Error = EntryError;
 
-        let password_policy: PasswordPolicy = policy.into();
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (policy, password) = input.split_once(':')
+            .ok_or(EntryError::MissingPassword)?;
 
-        Self {
-            policy: password_policy,
+        Ok(Self {
+            policy:   PasswordPolicy::try_from(policy)?,
             password: password.trim().into(),
-        }
+        })
+    }
+}
+
+impl fmt::Display for PasswordEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.policy, self.password)
     }
 }
 
 impl PasswordEntry {
-    fn compliant_part_one(&self) -> Compliance {
-        let letter = self.policy.letter();
-        let range = self.policy.min_max();
+    fn number(&self, number: &Number) -> usize {
+        match number {
+            Number::Literal(num) => *num,
+            Number::Min          => self.policy.min,
+            Number::Max          => self.policy.max,
+        }
+    }
+
+    fn text<'a>(&'a self, text: &'a Text) -> &'a str {
+        match text {
+            Text::Literal(text) => text,
+            Text::Letter        => &self.policy.letter,
+        }
+    }
+
+    // Evaluate a rule against the password, explaining the result. Positions
+    // outside the password don't hold, and keep the reason in the outcome.
+    fn evaluate(&self, rule: &Rule) -> Outcome {
+        match rule {
+            Rule::Count(text, min, max) => {
+                let text = self.text(text);
+                let count = self.password.matches(text).count();
+                let range = self.number(min) ..= self.number(max);
+
+                Outcome {
+                    holds:       range.contains(&count),
+                    explanation: format!(
+                        "'{}' appears {} times, allowed {:?}",
+                        text,
+                        count,
+                        range,
+                    ),
+                    invalid:     None,
+                }
+            },
+            Rule::At(pos, text) => {
+                let pos = self.number(pos);
+                let text = self.text(text);
+                let len = self.password.chars().count();
+
+                if pos == 0 {
+                    return Outcome::invalid("positions start at 1, got 0");
+                }
 
-        let count = self.password.chars()
-            .filter(|c| c.to_string() == letter)
-            .count();
+                if pos > len {
+                    return Outcome::invalid(&format!(
+                        "position {} is beyond password length {}",
+                        pos,
+                        len,
+                    ));
+                }
 
-        if range.contains(&count) {
-            Compliance::Compliant
+                // positions have no 0 index, so we offset by 1.
+                let found: String = self.password.chars()
+                    .skip(pos - 1)
+                    .take(text.chars().count())
+                    .collect();
+
+                let holds = found == text;
+
+                let explanation = if holds {
+                    format!("position {} is '{}'", pos, found)
+                }
+                else {
+                    format!("position {} is '{}', not '{}'", pos, found, text)
+                };
+
+                Outcome {
+                    holds,
+                    explanation,
+                    invalid: None,
+                }
+            },
+            Rule::Contains(text) => {
+                let text = self.text(text);
+                let holds = self.password.contains(text);
+
+                let explanation = if holds {
+                    format!("contains '{}'", text)
+                }
+                else {
+                    format!("doesn't contain '{}'", text)
+                };
+
+                Outcome {
+                    holds,
+                    explanation,
+                    invalid: None,
+                }
+            },
+            Rule::Forbid(text) => {
+                let text = self.text(text);
+                let holds = !self.password.contains(text);
+
+                let explanation = if holds {
+                    format!("doesn't contain forbidden '{}'", text)
+                }
+                else {
+                    format!("contains forbidden '{}'", text)
+                };
+
+                Outcome {
+                    holds,
+                    explanation,
+                    invalid: None,
+                }
+            },
+            Rule::Not(rule) => {
+                let inner = self.evaluate(rule);
+
+                Outcome {
+                    holds:       !inner.holds,
+                    explanation: format!("not ({})", inner.explanation),
+                    invalid:     inner.invalid,
+                }
+            },
+            Rule::And(rules) => {
+                let (held, explanation, invalid) = self.evaluate_all(rules);
+
+                Outcome {
+                    holds:       held == rules.len(),
+                    explanation: format!("all of ({})", explanation),
+                    invalid,
+                }
+            },
+            Rule::Or(rules) => {
+                let (held, explanation, invalid) = self.evaluate_all(rules);
+
+                Outcome {
+                    holds:       held > 0,
+                    explanation: format!("any of ({})", explanation),
+                    invalid,
+                }
+            },
+            Rule::Xor(rules) => {
+                let (held, explanation, invalid) = self.evaluate_all(rules);
+
+                Outcome {
+                    holds:       held == 1,
+                    explanation: format!(
+                        "exactly one of ({}), {} held",
+                        explanation,
+                        held,
+                    ),
+                    invalid,
+                }
+            },
         }
-        else {
-            Compliance::NonCompliant
+    }
+
+    // Evaluate every rule, returning how many held, their explanations and
+    // the first reason any of them couldn't be applied.
+    fn evaluate_all(
+        &self,
+        rules: &[Rule],
+    ) -> (usize, String, Option<String>) {
+        let mut held = 0;
+        let mut explanations = Vec::new();
+        let mut invalid = None;
+
+        for rule in rules {
+            let outcome = self.evaluate(rule);
+
+            if outcome.holds {
+                held += 1;
+            }
+
+            explanations.push(outcome.explanation);
+            invalid = invalid.or(outcome.invalid);
         }
+
+        (held, explanations.join("; "), invalid)
     }
 
-    fn compliant_part_two(&self) -> Compliance {
-        // positions have no 0 index, so we offset by 1.
-        let letter = self.policy.letter();
-        let pos_a = self.policy.pos_a() - 1;
-        let pos_b = self.policy.pos_b() - 1;
+    fn compliance(&self, rule: &Rule) -> Compliance {
+        self.explain(rule).0
+    }
 
-        let check = vec![
-            self.password.chars().nth(pos_a).unwrap(),
-            self.password.chars().nth(pos_b).unwrap(),
-        ];
+    // Compliance along with an explanation of why. A rule that doesn't hold
+    // because it couldn't be applied to the password is Invalid.
+    fn explain(&self, rule: &Rule) -> (Compliance, String) {
+        let outcome = self.evaluate(rule);
 
-        let count = check.iter()
-            .filter(|c| c.to_string() == letter)
-            .count();
+        match outcome {
+            Outcome { holds: true, explanation, .. } => {
+                (Compliance::Compliant, explanation)
+            },
+            Outcome { invalid: Some(reason), explanation, .. } => {
+                (Compliance::Invalid(reason), explanation)
+            },
+            Outcome { explanation, .. } => {
+                (Compliance::NonCompliant, explanation)
+            },
+        }
+    }
+}
 
-        if count == 1 {
-            Compliance::Compliant
+// Result of evaluating a rule against a password
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    holds:       bool,
+    explanation: String,
+
+    // Why part of the rule couldn't be applied to the password, if it
+    // couldn't.
+    invalid:     Option<String>,
+}
+
+impl Outcome {
+    // A rule that couldn't be applied, which doesn't hold
+    fn invalid(reason: &str) -> Self {
+        Self {
+            holds:       false,
+            explanation: reason.to_string(),
+            invalid:     Some(reason.to_string()),
         }
-        else {
-            Compliance::NonCompliant
+    }
+}
+
+// Parsed password database
+#[derive(Debug, Default)]
+struct Database {
+    entries: Vec<PasswordEntry>,
+
+    // Lines that couldn't be parsed, with their line number
+    skipped: Vec<(usize, EntryError)>,
+}
+
+impl Database {
+    // Report skipped lines on stderr
+    fn print_skipped(&self) {
+        if self.skipped.is_empty() {
+            return;
+        }
+
+        eprintln!("Skipped {} lines:", self.skipped.len());
+
+        for (line, error) in &self.skipped {
+            eprintln!("  Line {}: {}", line, error);
         }
     }
 }
@@ -141,44 +741,112 @@ fn input_reader(
     Ok(reader)
 }
 
-fn input_to_entries(input: &str) -> Vec<PasswordEntry> {
-    input.lines()
-        .map(|line| PasswordEntry::from(line))
-        .collect()
+fn input_to_database(input: &str) -> Database {
+    let mut database: Database = Default::default();
+
+    for (i, line) in input.lines().enumerate() {
+        match PasswordEntry::try_from(line) {
+            Ok(entry)  => database.entries.push(entry),
+            Err(error) => database.skipped.push((i + 1, error)),
+        }
+    }
+
+    database
 }
 
-fn part_one(input: &str) {
-    let password_entries = input_to_entries(&input);
+// Count compliant passwords, also returning the entries the rule couldn't
+// be applied to along with the reason.
+fn count_compliant<'a>(
+    entries: &'a [PasswordEntry],
+    rule: &Rule,
+) -> (usize, Vec<(&'a PasswordEntry, String)>) {
+    let mut count = 0;
+    let mut invalid = Vec::new();
 
-    let count = password_entries.iter()
-        .map(|e| e.compliant_part_one())
-        .filter(|c| c == &Compliance::Compliant)
-        .count();
+    for entry in entries {
+        match entry.compliance(rule) {
+            Compliance::Compliant       => count += 1,
+            Compliance::NonCompliant    => {},
+            Compliance::Invalid(reason) => invalid.push((entry, reason)),
+        }
+    }
 
-    println!("Found {} valid passwords", count);
+    (count, invalid)
 }
 
-fn part_two(input: &str) {
-    let password_entries = input_to_entries(&input);
+// Print non-compliant entries with the reason they failed the rule
+fn print_non_compliant(entries: &[PasswordEntry], rule: &Rule) {
+    for entry in entries {
+        let (compliance, explanation) = entry.explain(rule);
 
-    let count = password_entries.iter()
-        .map(|e| e.compliant_part_two())
-        .filter(|c| c == &Compliance::Compliant)
-        .count();
+        if compliance != Compliance::Compliant {
+            println!("{}: {}", entry, explanation);
+        }
+    }
+}
+
+fn check_policy(
+    entries: &[PasswordEntry],
+    policy: &str,
+    explain: bool,
+) -> Result<(), Box<dyn Error>> {
+    let rule = Rule::try_from(policy)?;
+
+    if explain {
+        println!("Policy: {}", policy);
+        print_non_compliant(entries, &rule);
+    }
+
+    let (count, invalid) = count_compliant(entries, &rule);
+
+    // When explaining, invalid entries were already printed above
+    if !explain {
+        for (entry, reason) in &invalid {
+            eprintln!("Invalid {}: {}", entry, reason);
+        }
+    }
 
     println!("Found {} valid passwords", count);
+
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let mut policies = vec![
+        PART_ONE_POLICY.to_string(),
+        PART_TWO_POLICY.to_string(),
+    ];
+
+    let mut explain = false;
+
+    for flag in &flags {
+        if let Some(policy) = flag.strip_prefix("--policy=") {
+            // Check the database against a custom policy
+            policies = vec![policy.to_string()];
+        }
+        else if flag == "--explain" {
+            explain = true;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
     // Get the input
     let mut buffer = String::new();
     let mut reader = input_reader(args)?;
     reader.read_to_string(&mut buffer)?;
 
-    part_one(&buffer);
-    part_two(&buffer);
+    let database = input_to_database(&buffer);
+    database.print_skipped();
+
+    for policy in &policies {
+        check_policy(&database.entries, policy, explain)?;
+    }
 
     Ok(())
 }
@@ -186,4 +854,179 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    // Taken from example on day 2
+    const EXAMPLE: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n";
+
+    fn check(rule: &str, entry: &str) -> bool {
+        let rule = Rule::try_from(rule).unwrap();
+        let entry = PasswordEntry::try_from(entry).unwrap();
+
+        entry.evaluate(&rule).holds
+    }
+
+    #[test]
+    fn test_part_one() {
+        let entries = input_to_database(EXAMPLE).entries;
+        let rule = Rule::try_from(PART_ONE_POLICY).unwrap();
+
+        assert_eq!(count_compliant(&entries, &rule).0, 2);
+    }
+
+    #[test]
+    fn test_part_two() {
+        let entries = input_to_database(EXAMPLE).entries;
+        let rule = Rule::try_from(PART_TWO_POLICY).unwrap();
+
+        assert_eq!(count_compliant(&entries, &rule).0, 1);
+    }
+
+    #[test]
+    fn test_rule_parse() {
+        let rule = Rule::try_from(r#"and(at(1, $letter), forbid("xy"))"#);
+
+        let expected = Rule::And(vec![
+            Rule::At(Number::Literal(1), Text::Letter),
+            Rule::Forbid(Text::Literal("xy".into())),
+        ]);
+
+        assert_eq!(rule, Ok(expected));
+
+        let invalid = vec![
+            "",
+            "count(a, 1)",
+            "count(a, b, 1)",
+            "at($letter, a)",
+            "and()",
+            "and(a)",
+            "nope(a)",
+            "not(at(1, a)",
+            "not(at(1, a)))",
+            "contains($nope)",
+            r#"contains("a)"#,
+        ];
+
+        for rule in invalid {
+            assert!(Rule::try_from(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_rule_evaluate() {
+        assert!(check("count(ab, 2, 2)", "1-3 a: abcab"));
+        assert!(!check("count(ab, 1, 1)", "1-3 a: abcab"));
+        assert!(check("at(3, ca)", "1-3 a: abcab"));
+        assert!(check("and(at($min, a), at($max, c))", "1-3 a: abcab"));
+        assert!(check("or(contains(z), contains(bc))", "1-3 a: abcab"));
+        assert!(check("forbid(z)", "1-3 a: abcab"));
+        assert!(!check("not(forbid(z))", "1-3 a: abcab"));
+        assert!(check("xor(at(1, a), at(2, a), at(3, a))", "1-3 a: abcab"));
+        assert!(check("count(1, 1, 1)", "1-3 a: a1b"));
+    }
+
+    #[test]
+    fn test_database_skipped() {
+        let input = concat!(
+            "1-3 a: abcde\n",
+            "1-3 b cdefg\n",
+            "1-x b: cdefg\n",
+            "13 b: cdefg\n",
+            "1-3: cdefg\n",
+            "2-9 c: ccccccccc\n",
+        );
+
+        let database = input_to_database(input);
+
+        let expected = vec![
+            (2, EntryError::MissingPassword),
+            (3, EntryError::BadNumber("x".into())),
+            (4, EntryError::BadRange("13".into())),
+            (5, EntryError::MissingLetter),
+        ];
+
+        assert_eq!(database.entries.len(), 2);
+        assert_eq!(database.skipped, expected);
+    }
+
+    #[test]
+    fn test_out_of_range_positions() {
+        let rule = Rule::try_from(PART_TWO_POLICY).unwrap();
+
+        let entry = PasswordEntry::try_from("1-9 b: abc").unwrap();
+        let expected = Compliance::Invalid(
+            "position 9 is beyond password length 3".into(),
+        );
+
+        assert_eq!(entry.compliance(&rule), expected);
+
+        let entry = PasswordEntry::try_from("0-2 a: abc").unwrap();
+
+        assert!(matches!(entry.compliance(&rule), Compliance::Invalid(_)));
+
+        // Already decided by the other half of the xor
+        let entry = PasswordEntry::try_from("1-9 a: abc").unwrap();
+
+        assert_eq!(entry.compliance(&rule), Compliance::Compliant);
+
+        let entry = PasswordEntry::try_from("1-3 a: abc").unwrap();
+        let rule = Rule::try_from("or(at(9, a), contains(a))").unwrap();
+
+        assert_eq!(entry.compliance(&rule), Compliance::Compliant);
+
+        let rule = Rule::try_from("not(at(9, a))").unwrap();
+        let outcome = entry.evaluate(&rule);
+
+        assert!(outcome.holds);
+        assert_eq!(entry.compliance(&rule), Compliance::Compliant);
+
+        let rule = Rule::try_from("at(9, a)").unwrap();
+        let expected = Outcome {
+            holds:       false,
+            explanation: "position 9 is beyond password length 3".into(),
+            invalid:     Some("position 9 is beyond password length 3".into()),
+        };
+
+        assert_eq!(entry.evaluate(&rule), expected);
+
+        let entries = vec![entry];
+        let rule = Rule::try_from("and(at(9, a), contains(a))").unwrap();
+        let (count, invalid) = count_compliant(&entries, &rule);
+
+        assert_eq!(count, 0);
+        assert_eq!(invalid.len(), 1);
+    }
+
+    #[test]
+    fn test_explain() {
+        let part_one = Rule::try_from(PART_ONE_POLICY).unwrap();
+        let part_two = Rule::try_from(PART_TWO_POLICY).unwrap();
+
+        let entry = PasswordEntry::try_from("1-3 a: aaaab").unwrap();
+        let expected = (
+            Compliance::NonCompliant,
+            "'a' appears 4 times, allowed 1..=3".to_string(),
+        );
+
+        assert_eq!(entry.explain(&part_one), expected);
+
+        let entry = PasswordEntry::try_from("1-3 c: cbc").unwrap();
+        let expected = (
+            Compliance::NonCompliant,
+            concat!(
+                "exactly one of (position 1 is 'c'; position 3 is 'c'), ",
+                "2 held",
+            ).to_string(),
+        );
+
+        assert_eq!(entry.explain(&part_two), expected);
+
+        let entry = PasswordEntry::try_from("1-3 c: cbd").unwrap();
+        let (compliance, explanation) = entry.explain(&part_two);
+
+        assert_eq!(compliance, Compliance::Compliant);
+        assert_eq!(explanation, concat!(
+            "exactly one of (position 1 is 'c'; ",
+            "position 3 is 'd', not 'c'), 1 held",
+        ));
+    }
 }
@@ -1,8 +1,17 @@
 // day
+use common::grid::{
+    Grid,
+    GridError,
+    Wrapping,
+};
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
 use std::fmt;
-use std::fs::File;
+use std::fs::{
+    self,
+    File,
+};
 use std::io::{
     self,
     prelude::*,
@@ -36,51 +45,37 @@ impl From<char> for Tile {
     }
 }
 
-#[derive(Debug, Default)]
-struct Worldstate(Vec<Vec<Tile>>);
+// The toboggan map, which repeats to the right forever
+#[derive(Debug)]
+struct Worldstate(Grid<Tile>);
 
 impl Worldstate {
     fn rows(&self) -> usize {
-        self.0.len()
+        self.0.rows()
     }
 
-    // Could panic if used before Worldstate is initialized
+    // Width of a single copy of the map
     fn cols(&self) -> usize {
-        self.0[0].len()
+        self.0.cols()
     }
 
-    fn row(&self, row: usize) -> &[Tile] {
-        &self.0[row]
-    }
-
-    // Could panic if used before Worldstate is initialized
-    fn tile(&self, row: usize, col: usize) -> &Tile {
-        let row = self.row(row);
-        let tile = &row[col];
-
-        tile
+    // None if the row is beyond the bottom of the map
+    fn tile(&self, row: usize, col: usize) -> Option<&Tile> {
+        self.0.get(row, col)
     }
 }
 
-impl From<String> for Worldstate {
-    fn from(input: String) -> Self {
-        let mut state: Self = Default::default();
+impl TryFrom<&str> for Worldstate {
+    type Error = GridError;
 
-        for line in input.lines() {
-            let row = line.chars()
-                .map(|c| Tile::from(c))
-                .collect();
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let grid = Grid::try_from(input)?
+            .with_wrapping(Wrapping::Horizontal);
 
-            state.0.push(row);
-        }
-
-        state
+        Ok(Self(grid))
     }
 }
 
-#[derive(Debug)]
-struct Worldrow(Vec<Tile>);
-
 // CLI arguments
 type Args = Vec<String>;
 
@@ -104,9 +99,57 @@ fn input_reader(
     Ok(reader)
 }
 
-fn part_one(state: &Worldstate, row_step: usize, col_step: usize) -> usize {
+// How far the toboggan moves each step
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Slope {
+    right: usize,
+    down:  usize,
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "right {}, down {}", self.right, self.down)
+    }
+}
+
+impl TryFrom<&str> for Slope {
+    type Error = Box<dyn Error>;
+
+    // Slopes are given as RIGHT,DOWN
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (right, down) = input.trim().split_once(',')
+            .ok_or_else(|| format!("Invalid slope: {}", input))?;
+
+        let slope = Self {
+            right: right.trim().parse()?,
+            down:  down.trim().parse()?,
+        };
+
+        // We'd never reach the bottom
+        if slope.down == 0 {
+            return Err(format!("Slope must go down: {}", input).into());
+        }
+
+        Ok(slope)
+    }
+}
+
+// Slope used for part 1
+const PART_ONE_SLOPE: Slope = Slope { right: 3, down: 1 };
+
+// Slopes used for part 2
+const PART_TWO_SLOPES: &[Slope] = &[
+    Slope { right: 1, down: 1 },
+    Slope { right: 3, down: 1 },
+    Slope { right: 5, down: 1 },
+    Slope { right: 7, down: 1 },
+    Slope { right: 1, down: 2 },
+];
+
+// Count the trees hit going down the slope from the top left
+fn trees_hit(state: &Worldstate, slope: &Slope) -> usize {
     let total_rows = state.rows();
-    let total_cols = state.cols();
+    let total_cols = state.cols().max(1);
 
     let mut row = 0;
     let mut col = 0;
@@ -114,65 +157,203 @@ fn part_one(state: &Worldstate, row_step: usize, col_step: usize) -> usize {
 
     loop {
         // Go down a number of rows
-        row += row_step;
+        row += slope.down;
 
         // Bounds check
         if row >= total_rows {
             break
         }
 
-        // Go right a number of columns
-        col += col_step;
-
-        // Wrapping
-        col %= total_cols;
-
-        // Get the tile
-        let tile = state.tile(row, col);
+        // Go right a number of columns, wrapping around the map. Reduce
+        // the step first so large slopes can't overflow.
+        col = (col + (slope.right % total_cols)) % total_cols;
 
         // Is it a tree?
-        if tile == &Tile::Tree {
+        if state.tile(row, col) == Some(&Tile::Tree) {
             trees += 1;
         }
     }
 
-    println!("Part 1: Encountered {} trees", trees);
-
     trees
 }
 
+// ANSI colour codes for rendering
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// Render the map with the path down the slope overlaid. Open squares visited
+// are marked O and trees hit are marked X. The map is repeated to the right
+// as far as the path goes.
+fn render(state: &Worldstate, slope: &Slope, colour: bool) -> String {
+    let total_rows = state.rows();
+    let total_cols = state.cols();
+
+    // Furthest right we'll go, then round up to whole copies of the map.
+    let steps = total_rows.saturating_sub(1) / slope.down;
+    let last_col = steps * slope.right;
+    let copies = (last_col / total_cols.max(1)) + 1;
+    let width = copies * total_cols;
+
+    let mut output = String::new();
+
+    for row in 0 .. total_rows {
+        // The column the path is in on this row, if it lands on it
+        let path_col = if row > 0 && row % slope.down == 0 {
+            Some((row / slope.down) * slope.right)
+        }
+        else {
+            None
+        };
+
+        for col in 0 .. width {
+            let tile = match state.tile(row, col) {
+                Some(tile) => tile,
+                None       => continue,
+            };
+
+            if path_col != Some(col) {
+                output.push_str(&tile.to_string());
+                continue;
+            }
+
+            let (mark, ansi) = match tile {
+                Tile::Ground => ('O', ANSI_GREEN),
+                Tile::Tree   => ('X', ANSI_RED),
+            };
+
+            if colour {
+                output.push_str(ansi);
+                output.push(mark);
+                output.push_str(ANSI_RESET);
+            }
+            else {
+                output.push(mark);
+            }
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+// Trees hit for every slope from right 0 to max.right and down 1 to
+// max.down.
+fn search(state: &Worldstate, max: &Slope) -> Vec<(Slope, usize)> {
+    let mut results = Vec::new();
+
+    for down in 1 ..= max.down {
+        for right in 0 ..= max.right {
+            let slope = Slope { right, down };
+            results.push((slope, trees_hit(state, &slope)));
+        }
+    }
+
+    results
+}
+
+// Print trees hit per slope, the best and worst slopes and the product
+fn report(state: &Worldstate, slopes: &[Slope]) {
+    let results: Vec<(Slope, usize)> = slopes.iter()
+        .map(|slope| (*slope, trees_hit(state, slope)))
+        .collect();
+
+    print_results(&results);
+}
+
+fn print_results(results: &[(Slope, usize)]) {
+    for (slope, trees) in results {
+        println!("Slope {}: {} trees", slope, trees);
+    }
+
+    let fewest = results.iter().min_by_key(|(_, trees)| *trees);
+    let most = results.iter().max_by_key(|(_, trees)| *trees);
+
+    if let (Some(fewest), Some(most)) = (fewest, most) {
+        println!("Fewest trees: {} ({} trees)", fewest.0, fewest.1);
+        println!("Most trees: {} ({} trees)", most.0, most.1);
+    }
+
+    // Searching lots of slopes can easily overflow
+    let product = results.iter()
+        .try_fold(1usize, |acc, (_, trees)| acc.checked_mul(*trees));
+
+    match product {
+        Some(product) => println!("Product: {}", product),
+        None          => println!("Product: Too large"),
+    }
+}
+
+// Read slopes from a file, one per line
+fn slopes_from_file(filename: &str) -> Result<Vec<Slope>, Box<dyn Error>> {
+    let input = fs::read_to_string(filename)?;
+
+    input.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Slope::try_from)
+        .collect()
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let mut slopes = Vec::new();
+    let mut search_max = None;
+    let mut render_path = false;
+    let mut colour = false;
+
+    for flag in &flags {
+        if let Some(slope) = flag.strip_prefix("--slope=") {
+            slopes.push(Slope::try_from(slope)?);
+        }
+        else if let Some(filename) = flag.strip_prefix("--slopes-file=") {
+            slopes.extend(slopes_from_file(filename)?);
+        }
+        else if let Some(max) = flag.strip_prefix("--search=") {
+            search_max = Some(Slope::try_from(max)?);
+        }
+        else if flag == "--render" {
+            render_path = true;
+        }
+        else if flag == "--colour" {
+            colour = true;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
     // Get the input
     let mut buffer = String::new();
     let mut reader = input_reader(args)?;
     reader.read_to_string(&mut buffer)?;
 
-    let state: Worldstate = buffer.into();
-    //println!("{:?}", state);
-
-    part_one(&state, 1, 3);
+    let state = Worldstate::try_from(buffer.as_str())?;
 
-    // Reuse our part 1 function for part 2 here.
-    let slopes = vec![
-        (1, 1),
-        (1, 3),
-        (1, 5),
-        (1, 7),
-        (2, 1),
-    ];
-
-    let mut slope_trees = Vec::new();
-
-    for (row_step, col_step) in slopes {
-        let trees = part_one(&state, row_step, col_step);
-        slope_trees.push(trees);
+    if render_path {
+        // Render the first slope given, or the part 1 slope
+        let slope = slopes.first().unwrap_or(&PART_ONE_SLOPE);
+        print!("{}", render(&state, slope, colour));
+    }
+    else if let Some(max) = search_max {
+        print_results(&search(&state, &max));
+    }
+    else if !slopes.is_empty() {
+        report(&state, &slopes);
     }
+    else {
+        let trees = trees_hit(&state, &PART_ONE_SLOPE);
+        println!("Part 1: Encountered {} trees", trees);
 
-    let total: usize = slope_trees.iter().product();
+        let total: usize = PART_TWO_SLOPES.iter()
+            .map(|slope| trees_hit(&state, slope))
+            .product();
 
-    println!("Part 2: {}", total);
+        println!("Part 2: {}", total);
+    }
 
     Ok(())
 }
@@ -180,4 +361,94 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    // Taken from example on day 3
+    const EXAMPLE: &str = concat!(
+        "..##.......\n",
+        "#...#...#..\n",
+        ".#....#..#.\n",
+        "..#.#...#.#\n",
+        ".#...##..#.\n",
+        "..#.##.....\n",
+        ".#.#.#....#\n",
+        ".#........#\n",
+        "#.##...#...\n",
+        "#...##....#\n",
+        ".#..#...#.#\n",
+    );
+
+    #[test]
+    fn test_part_one() {
+        let state = Worldstate::try_from(EXAMPLE).unwrap();
+
+        assert_eq!(trees_hit(&state, &PART_ONE_SLOPE), 7);
+    }
+
+    #[test]
+    fn test_part_two() {
+        let state = Worldstate::try_from(EXAMPLE).unwrap();
+
+        let trees: Vec<usize> = PART_TWO_SLOPES.iter()
+            .map(|slope| trees_hit(&state, slope))
+            .collect();
+
+        assert_eq!(trees, vec![2, 7, 3, 4, 2]);
+    }
+
+    #[test]
+    fn test_large_slope() {
+        let state = Worldstate::try_from(EXAMPLE).unwrap();
+        let huge = Slope { right: usize::MAX, down: 1 };
+        let wrapped = Slope { right: usize::MAX % state.cols(), down: 1 };
+
+        assert_eq!(trees_hit(&state, &huge), trees_hit(&state, &wrapped));
+    }
+
+    #[test]
+    fn test_search() {
+        let state = Worldstate::try_from(EXAMPLE).unwrap();
+        let max = Slope::try_from("7,2").unwrap();
+        let results = search(&state, &max);
+
+        assert_eq!(results.len(), 16);
+        assert!(results.contains(&(Slope { right: 3, down: 1 }, 7)));
+        assert!(results.contains(&(Slope { right: 1, down: 2 }, 2)));
+    }
+
+    #[test]
+    fn test_slope_from() {
+        assert_eq!(Slope::try_from(" 3, 1").unwrap(), PART_ONE_SLOPE);
+        assert!(Slope::try_from("3,0").is_err());
+        assert!(Slope::try_from("3").is_err());
+        assert!(Slope::try_from("a,1").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let state = Worldstate::try_from(EXAMPLE).unwrap();
+        let output = render(&state, &PART_ONE_SLOPE, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Taken from example on day 3, which repeats the map further than
+        // we need to.
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "..##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(lines[10], ".#..#...#.#.#..#...#.#.#..#...X.#");
+
+        let trees = output.matches('X').count();
+        assert_eq!(trees, trees_hit(&state, &PART_ONE_SLOPE));
+
+        let output = render(&state, &PART_ONE_SLOPE, true);
+        assert!(output.lines().nth(1).unwrap().contains("\x1b[32mO\x1b[0m"));
+    }
+
+    #[test]
+    fn test_empty_map() {
+        let state = Worldstate::try_from("").unwrap();
+
+        assert_eq!(trees_hit(&state, &PART_ONE_SLOPE), 0);
+        assert_eq!(render(&state, &PART_ONE_SLOPE, false), "");
+    }
 }
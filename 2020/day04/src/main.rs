@@ -1,4 +1,5 @@
 // day
+use common::input::grouped_records;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -14,6 +15,26 @@ enum HeightType {
     Inches(u64),
 }
 
+impl From<&str> for HeightType {
+    fn from(input: &str) -> Self {
+        // Collect the height, ignoring the suffix.
+        let height: String = input.chars()
+            .filter(|c| c.is_numeric())
+            .collect();
+
+        // Parse the height string to a u64
+        let height: u64 = height.parse().unwrap();
+
+        // Check the suffix to see which height type we have
+        if input.ends_with("cm") {
+            Self::Centimetres(height)
+        }
+        else {
+            Self::Inches(height)
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Detail {
     BirthYear(u64),
@@ -38,25 +59,7 @@ impl From<&str> for Detail {
             "eyr" => Self::ExpirationYear(value.parse().unwrap()),
             "ecl" => Self::EyeColour(value.into()),
             "hcl" => Self::HairColour(value.into()),
-            "hgt" => {
-                // Collect the height, ignoring the suffix.
-                let height: String = value.chars()
-                    .filter(|c| c.is_numeric())
-                    .collect();
-
-                // Parse the height string to a u64
-                let height: u64 = height.parse().unwrap();
-
-                // Check the suffix to see which height type we have
-                let height_type = if value.ends_with("cm") {
-                    HeightType::Centimetres(height)
-                }
-                else {
-                    HeightType::Inches(height)
-                };
-
-                Self::Height(height_type)
-            },
+            "hgt" => Self::Height(value.into()),
             "iyr" => Self::IssueYear(value.parse().unwrap()),
             "pid" => Self::PassportId(value.into()),
             _     => panic!("Unknown password field"),
@@ -372,12 +375,11 @@ fn input_to_passports(input: &str) -> Vec<Passport> {
     // Accumulates passports as we parse them
     let mut passports: Vec<Passport> = Vec::new();
 
-    // This is the active passport. This will eventually end up in passports
-    // and be reset.
-    let mut passport: Passport = Default::default();
+    // Each group of lines is a single passport
+    for record in grouped_records(input) {
+        let mut passport: Passport = Default::default();
 
-    for line in input.lines() {
-        if line.len() > 0 {
+        for line in record {
             let details: Vec<&str> = line.split(' ').collect();
 
             for detail in details.into_iter() {
@@ -411,13 +413,10 @@ fn input_to_passports(input: &str) -> Vec<Passport> {
                 }
             }
         }
-        else {
-            passports.push(passport);
-            passport = Default::default();
-        }
+
+        passports.push(passport);
     }
 
-    passports.push(passport);
     passports
 }
 
@@ -508,4 +507,43 @@ mod test {
 
         assert_eq!(valid_count, 2);
     }
+
+    #[test]
+    fn test_height_type_from() {
+        let cm = HeightType::from("183cm");
+        let inches = HeightType::from("74in");
+
+        assert!(matches!(cm, HeightType::Centimetres(183)));
+        assert!(matches!(inches, HeightType::Inches(74)));
+    }
+
+    #[test]
+    fn test_input_to_passports() {
+        // Taken from example on day 4
+        let input = concat!(
+            "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\n",
+            "byr:1937 iyr:2017 cid:147 hgt:183cm\n",
+            "\n",
+            "iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\n",
+            "hcl:#cfa07d byr:1929\n",
+            "\n",
+            "hcl:#ae17e1 iyr:2013\n",
+            "eyr:2024\n",
+            "ecl:brn pid:760753108 byr:1931\n",
+            "hgt:179cm\n",
+            "\n",
+            "hcl:#cfa07d eyr:2025 pid:166559648\n",
+            "iyr:2011 ecl:brn hgt:59in\n",
+        );
+
+        let passports = input_to_passports(input);
+
+        let valid_count = passports.iter()
+            .map(|p| p.validate_one())
+            .filter(|v| *v == Validation::Valid)
+            .count();
+
+        assert_eq!(passports.len(), 4);
+        assert_eq!(valid_count, 2);
+    }
 }
@@ -1,5 +1,5 @@
 // day
-use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
 use std::fmt;
@@ -10,7 +10,7 @@ use std::io::{
     BufReader,
 };
 
-#[derive(Debug, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 struct SeatId(u64);
 
 impl fmt::Display for SeatId {
@@ -19,101 +19,423 @@ impl fmt::Display for SeatId {
     }
 }
 
-impl Ord for SeatId {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+#[derive(Debug, PartialEq)]
+enum SeatError {
+    // Layout row or column count wasn't a power of two
+    Layout(u64),
+
+    // Row multiplier would give more than one seat the same ID
+    Multiplier {
+        multiplier: u64,
+        cols:       u64,
+    },
+
+    // Seat IDs or the seat map wouldn't fit for this layout
+    TooLarge {
+        rows:       u64,
+        cols:       u64,
+        multiplier: u64,
+    },
+
+    // Boarding pass code was the wrong length
+    Length {
+        expected: usize,
+        found:    usize,
+    },
+
+    // Unexpected character at the given position
+    Character(usize, char),
+
+    // Seat doesn't exist in the layout
+    OutOfRange(u64, u64),
+
+    // More than one boarding pass for the same seat
+    Duplicate(String),
+}
+
+impl fmt::Display for SeatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Layout(n) => {
+                write!(f, "Layout size {} is not a power of two", n)
+            },
+            Self::Multiplier { multiplier, cols } => {
+                write!(
+                    f,
+                    "Row multiplier {} is less than {} columns",
+                    multiplier,
+                    cols,
+                )
+            },
+            Self::TooLarge { rows, cols, multiplier } => {
+                write!(
+                    f,
+                    "Layout {}x{}:{} is too large",
+                    rows,
+                    cols,
+                    multiplier,
+                )
+            },
+            Self::Length { expected, found } => {
+                write!(f, "Expected {} characters, got {}", expected, found)
+            },
+            Self::Character(pos, c) => {
+                write!(f, "Unknown seat code '{}' at position {}", c, pos)
+            },
+            Self::OutOfRange(row, col) => {
+                write!(f, "Seat at row {}, column {} is out of range", row, col)
+            },
+            Self::Duplicate(code) => {
+                write!(f, "Duplicate boarding pass {}", code)
+            },
+        }
+    }
+}
+
+impl Error for SeatError {}
+
+// Describes the seating on a plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SeatLayout {
+    rows: u64,
+    cols: u64,
+
+    // SeatIds are row * row_multiplier + col
+    row_multiplier: u64,
+}
+
+impl Default for SeatLayout {
+    fn default() -> Self {
+        Self {
+            rows:           128,
+            cols:           8,
+            row_multiplier: 8,
+        }
     }
 }
 
-impl From<&str> for SeatId {
-    fn from(input: &str) -> Self {
-        let mut rows: Vec<u64> = (0 ..= 127).collect();
-        let mut cols: Vec<u64> = (0 ..= 7).collect();
+impl SeatLayout {
+    // A layout with IDs of row * row_multiplier + col. The usual formula
+    // has a row_multiplier equal to cols, giving contiguous IDs.
+    fn new(
+        rows: u64,
+        cols: u64,
+        row_multiplier: u64,
+    ) -> Result<Self, SeatError> {
+        for n in &[rows, cols] {
+            if !n.is_power_of_two() {
+                return Err(SeatError::Layout(*n));
+            }
+        }
+
+        // Any smaller and IDs would overlap the next row
+        if row_multiplier < cols {
+            return Err(SeatError::Multiplier {
+                multiplier: row_multiplier,
+                cols,
+            });
+        }
+
+        let layout = Self {
+            rows,
+            cols,
+            row_multiplier,
+        };
+
+        // The last seat has the largest ID
+        let max_id = (rows - 1).checked_mul(row_multiplier)
+            .and_then(|id| id.checked_add(cols - 1));
 
-        for c in input.chars() {
-            match c {
-                'F' => {
-                    let len = rows.len();
-                    let split = len / 2;
+        if max_id.is_none() {
+            return Err(layout.too_large());
+        }
+
+        Ok(layout)
+    }
+
+    fn too_large(&self) -> SeatError {
+        SeatError::TooLarge {
+            rows:       self.rows,
+            cols:       self.cols,
+            multiplier: self.row_multiplier,
+        }
+    }
+
+    // Length of the row part of a boarding pass code
+    fn row_bits(&self) -> usize {
+        self.rows.trailing_zeros() as usize
+    }
+
+    // Length of the column part of a boarding pass code
+    fn col_bits(&self) -> usize {
+        self.cols.trailing_zeros() as usize
+    }
+
+    fn code_len(&self) -> usize {
+        self.row_bits() + self.col_bits()
+    }
+
+    fn seat_id(&self, seat: &Seat) -> SeatId {
+        SeatId((seat.row * self.row_multiplier) + seat.col)
+    }
+
+    // The inverse of seat_id. When the row multiplier is larger than the
+    // number of columns, some IDs have no seat.
+    fn seat(&self, seat_id: &SeatId) -> Option<Seat> {
+        let seat = Seat {
+            row: seat_id.0 / self.row_multiplier,
+            col: seat_id.0 % self.row_multiplier,
+        };
+
+        if seat.row < self.rows && seat.col < self.cols {
+            Some(seat)
+        }
+        else {
+            None
+        }
+    }
 
-                    // Split "seats", we don't need this half since we want
-                    // the front half that's left in "seats".
-                    let _ = rows.split_off(split);
-                },
-                'B' => {
-                    let len = rows.len();
-                    let split = len / 2;
+    // Boarding pass codes are binary numbers, F and L are 0, B and R are 1.
+    fn decode(&self, input: &str) -> Result<Seat, SeatError> {
+        let len = input.chars().count();
 
-                    // With the back half, we want the new split.
-                    let new = rows.split_off(split);
-                    rows = new;
-                },
-                'L' => {
-                    let len = cols.len();
-                    let split = len / 2;
+        if len != self.code_len() {
+            return Err(SeatError::Length {
+                expected: self.code_len(),
+                found:    len,
+            });
+        }
 
-                    let _ = cols.split_off(split);
-                },
-                'R' => {
-                    let len = cols.len();
-                    let split = len / 2;
+        let mut row = 0;
+        let mut col = 0;
 
-                    let new = cols.split_off(split);
-                    cols = new;
-                },
-                _ => panic!("Unknown seat assignment code"),
+        for (pos, c) in input.chars().enumerate() {
+            match (pos < self.row_bits(), c) {
+                (true, 'F')  => row <<= 1,
+                (true, 'B')  => row = (row << 1) | 1,
+                (false, 'L') => col <<= 1,
+                (false, 'R') => col = (col << 1) | 1,
+                _            => return Err(SeatError::Character(pos, c)),
             }
         }
 
-        // Should only be a single row and column left now.
-        let row = rows[0];
-        let col = cols[0];
-        let seat_id = (row * 8) + col;
+        Ok(Seat {
+            row,
+            col,
+        })
+    }
+
+    // Encode a seat back to its boarding pass code
+    fn encode(&self, seat: &Seat) -> Result<String, SeatError> {
+        if seat.row >= self.rows || seat.col >= self.cols {
+            return Err(SeatError::OutOfRange(seat.row, seat.col));
+        }
+
+        let row = (0 .. self.row_bits()).rev()
+            .map(|bit| if seat.row >> bit & 1 == 1 { 'B' } else { 'F' });
+
+        let col = (0 .. self.col_bits()).rev()
+            .map(|bit| if seat.col >> bit & 1 == 1 { 'R' } else { 'L' });
+
+        Ok(row.chain(col).collect())
+    }
+}
+
+impl TryFrom<&str> for SeatLayout {
+    type Error = Box<dyn Error>;
+
+    // Layouts are given as ROWSxCOLS, optionally followed by :MULTIPLIER to
+    // change the row multiplier in the ID formula.
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (size, multiplier) = match input.split_once(':') {
+            Some((size, multiplier)) => (size, Some(multiplier)),
+            None                     => (input, None),
+        };
+
+        let (rows, cols) = size.split_once('x')
+            .ok_or_else(|| format!("Invalid layout: {}", input))?;
+
+        let cols: u64 = cols.parse()?;
+        let multiplier = match multiplier {
+            Some(multiplier) => multiplier.parse()?,
+            None             => cols,
+        };
+
+        let layout = Self::new(rows.parse()?, cols, multiplier)?;
+
+        Ok(layout)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Seat {
+    row: u64,
+    col: u64,
+}
+
+impl TryFrom<&str> for Seat {
+    type Error = SeatError;
 
-        Self(seat_id)
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        SeatLayout::default().decode(input)
+    }
+}
+
+impl TryFrom<&str> for SeatId {
+    type Error = SeatError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let layout = SeatLayout::default();
+        let seat = layout.decode(input)?;
+
+        Ok(layout.seat_id(&seat))
     }
 }
 
 // CLI arguments
 type Args = Vec<String>;
 
-fn part_one(input: &str) {
-    let seat_ids: Vec<SeatId> = input.lines()
-        .map(|line| SeatId::from(line))
-        .collect();
+// Which seats on the plane are occupied
+#[derive(Debug)]
+struct SeatMap {
+    layout:   SeatLayout,
+    occupied: Vec<bool>,
+}
+
+impl SeatMap {
+    // Fails if there are too many seats to hold in memory
+    fn new(layout: &SeatLayout) -> Result<Self, SeatError> {
+        let size = layout.rows.checked_mul(layout.cols)
+            .and_then(|size| isize::try_from(size).ok())
+            .ok_or_else(|| layout.too_large())?;
+
+        Ok(Self {
+            layout:   *layout,
+            occupied: vec![false; size as usize],
+        })
+    }
+
+    fn index(&self, row: u64, col: u64) -> usize {
+        ((row * self.layout.cols) + col) as usize
+    }
+
+    // Mark a seat as occupied, failing if it already was.
+    fn insert(&mut self, seat: &Seat) -> Result<(), SeatError> {
+        let code = self.layout.encode(seat)?;
+        let index = self.index(seat.row, seat.col);
+
+        if self.occupied[index] {
+            return Err(SeatError::Duplicate(code));
+        }
+
+        self.occupied[index] = true;
+
+        Ok(())
+    }
+
+    fn is_occupied(&self, seat: &Seat) -> bool {
+        seat.row < self.layout.rows
+            && seat.col < self.layout.cols
+            && self.occupied[self.index(seat.row, seat.col)]
+    }
+
+    // IDs without a seat are never occupied
+    fn is_occupied_id(&self, seat_id: &SeatId) -> bool {
+        self.layout.seat(seat_id)
+            .is_some_and(|seat| self.is_occupied(&seat))
+    }
+
+    // Every seat on the plane in row order
+    fn seats(&self) -> impl Iterator<Item = Seat> + '_ {
+        (0 .. self.layout.rows).flat_map(move |row| {
+            (0 .. self.layout.cols).map(move |col| Seat { row, col })
+        })
+    }
+
+    fn seat_ids(&self) -> Vec<SeatId> {
+        self.seats()
+            .filter(|seat| self.is_occupied(seat))
+            .map(|seat| self.layout.seat_id(&seat))
+            .collect()
+    }
+
+    // Empty seats where the seats with the IDs either side are occupied. If
+    // the row multiplier leaves IDs without seats, seats next to them can't
+    // be gaps.
+    fn gaps(&self) -> Vec<SeatId> {
+        self.seats()
+            .filter(|seat| !self.is_occupied(seat))
+            .map(|seat| self.layout.seat_id(&seat))
+            .filter(|id| {
+                let before = id.0.checked_sub(1).map(SeatId);
+                let after = id.0.checked_add(1).map(SeatId);
+
+                before.is_some_and(|id| self.is_occupied_id(&id))
+                    && after.is_some_and(|id| self.is_occupied_id(&id))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0 .. self.layout.rows {
+            let seats: String = (0 .. self.layout.cols)
+                .map(|col| {
+                    if self.is_occupied(&Seat { row, col }) { '#' } else { '.' }
+                })
+                .collect();
+
+            writeln!(f, "{:>4} {}", row, seats)?;
+        }
+
+        Ok(())
+    }
+}
 
-    let max = seat_ids.iter()
-        .max()
-        .unwrap();
+fn input_to_seat_map(
+    input: &str,
+    layout: &SeatLayout,
+) -> Result<SeatMap, Box<dyn Error>> {
+    let mut map = SeatMap::new(layout)?;
 
-    println!("Part 1: {}", max);
+    for (i, line) in input.lines().enumerate() {
+        layout.decode(line)
+            .and_then(|seat| map.insert(&seat))
+            .map_err(|e| format!("Line {}: {}", i + 1, e))?;
+    }
+
+    Ok(map)
 }
 
-fn part_two(input: &str) {
-    let seat_ids: Vec<SeatId> = input.lines()
-        .map(|line| SeatId::from(line))
-        .collect();
+fn part_one(map: &SeatMap) {
+    let max = map.seat_ids()
+        .into_iter()
+        .max();
 
-    let min = seat_ids.iter()
-        .min()
-        .unwrap();
+    match max {
+        Some(max) => println!("Part 1: {}", max),
+        None      => println!("Part 1: No boarding passes"),
+    }
+}
 
-    let max = seat_ids.iter()
-        .max()
-        .unwrap();
+fn part_two(map: &SeatMap) -> Result<(), Box<dyn Error>> {
+    let gaps = map.gaps();
 
-    // The sum if every seat was full
-    let clean_sum: u64 = (min.0 ..= max.0).sum();
+    // There should be exactly one gap, which is our seat.
+    for seat_id in &gaps {
+        let seat = map.layout.seat(seat_id)
+            .ok_or_else(|| format!("No seat with SeatId {}", seat_id))?;
+        let code = map.layout.encode(&seat)?;
 
-    // The sum with a missing seat
-    let real_sum: u64 = seat_ids.iter()
-        .fold(0, |acc, x| acc + x.0);
+        println!("Part 2: SeatId {} ({})", seat_id, code);
+    }
 
-    // Find the difference between the two, this should be our seat number.
-    let seat_id = clean_sum - real_sum;
+    if gaps.len() != 1 {
+        println!("Part 2: Expected 1 empty seat, found {}", gaps.len());
+    }
 
-    println!("Part 2: Min {}, Max {}", min, max);
-    println!("Part 2: SeatId {}", seat_id);
+    Ok(())
 }
 
 // Get an input reader
@@ -137,15 +459,38 @@ fn input_reader(
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let mut layout = SeatLayout::default();
+    let mut show_map = false;
+
+    for flag in flags {
+        if let Some(size) = flag.strip_prefix("--layout=") {
+            layout = SeatLayout::try_from(size)?;
+        }
+        else if flag == "--map" {
+            show_map = true;
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
     // Get the input
     let mut buffer = String::new();
     let mut reader = input_reader(args)?;
     reader.read_to_string(&mut buffer)?;
 
-    part_one(&buffer);
-    part_two(&buffer);
+    let map = input_to_seat_map(&buffer, &layout)?;
+
+    if show_map {
+        print!("{}", map);
+    }
+
+    part_one(&map);
+    part_two(&map)?;
 
     Ok(())
 }
@@ -159,8 +504,179 @@ mod test {
         let input = "FBFBBFFRLR";
         let expected = SeatId(357);
 
-        let seat_id: SeatId = input.into();
+        let seat_id = SeatId::try_from(input).unwrap();
 
         assert_eq!(seat_id, expected);
     }
+
+    #[test]
+    fn test_seat_from() {
+        // Taken from example on day 5
+        let tests = vec![
+            ("FBFBBFFRLR", Seat { row: 44, col: 5 }, 357),
+            ("BFFFBBFRRR", Seat { row: 70, col: 7 }, 567),
+            ("FFFBBBFRRR", Seat { row: 14, col: 7 }, 119),
+            ("BBFFBBFRLL", Seat { row: 102, col: 4 }, 820),
+        ];
+
+        for (input, expected, id) in tests {
+            let seat = Seat::try_from(input).unwrap();
+
+            assert_eq!(seat, expected);
+            assert_eq!(SeatLayout::default().seat_id(&seat), SeatId(id));
+        }
+    }
+
+    #[test]
+    fn test_seat_from_invalid() {
+        let tests = vec![
+            ("FBFBBFFRL", SeatError::Length { expected: 10, found: 9 }),
+            ("FBFBBFFRLRR", SeatError::Length { expected: 10, found: 11 }),
+            ("FBFBBFFRLX", SeatError::Character(9, 'X')),
+            ("FBFBBFRRLR", SeatError::Character(6, 'R')),
+            ("FBFBBFFFLR", SeatError::Character(7, 'F')),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(Seat::try_from(input), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_seat_round_trip() {
+        let layouts = vec![
+            SeatLayout::default(),
+            SeatLayout::new(4, 4, 4).unwrap(),
+            SeatLayout::new(1, 2, 2).unwrap(),
+        ];
+
+        for layout in layouts {
+            for row in 0 .. layout.rows {
+                for col in 0 .. layout.cols {
+                    let seat = Seat { row, col };
+                    let code = layout.encode(&seat).unwrap();
+
+                    assert_eq!(code.len(), layout.code_len());
+                    assert_eq!(layout.decode(&code), Ok(seat));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seat_layout() {
+        let layout = SeatLayout::try_from("16x4").unwrap();
+        let seat = layout.decode("BFBFRL").unwrap();
+
+        assert_eq!(seat, Seat { row: 10, col: 2 });
+        assert_eq!(layout.seat_id(&seat), SeatId(42));
+        assert_eq!(layout.seat(&SeatId(42)), Some(seat));
+        assert_eq!(layout.encode(&Seat { row: 16, col: 0 }),
+            Err(SeatError::OutOfRange(16, 0)));
+
+        assert_eq!(SeatLayout::new(100, 8, 8), Err(SeatError::Layout(100)));
+        assert!(SeatLayout::try_from("128").is_err());
+
+        let layout = SeatLayout::try_from("16x4:10").unwrap();
+        let seat = Seat { row: 10, col: 2 };
+
+        assert_eq!(layout.seat_id(&seat), SeatId(102));
+        assert_eq!(layout.seat(&SeatId(102)), Some(seat));
+        assert_eq!(layout.seat(&SeatId(105)), None);
+        assert_eq!(layout.seat(&SeatId(160)), None);
+
+        let expected = SeatError::Multiplier {
+            multiplier: 2,
+            cols:       4,
+        };
+
+        assert_eq!(SeatLayout::new(16, 4, 2), Err(expected));
+        assert!(SeatLayout::try_from("16x4:x").is_err());
+
+        let expected = SeatError::TooLarge {
+            rows:       2,
+            cols:       2,
+            multiplier: u64::MAX,
+        };
+
+        assert_eq!(SeatLayout::new(2, 2, u64::MAX), Err(expected));
+        assert!(SeatLayout::try_from("2x2:18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn test_seat_map_too_large() {
+        // Every seat ID fits, but there are too many seats to hold
+        let size = 1 << 32;
+        let layout = SeatLayout::new(size, size, size).unwrap();
+
+        assert!(SeatMap::new(&layout).is_err());
+
+        // The last seat has the largest possible ID
+        let layout = SeatLayout::try_from("2x1:18446744073709551615").unwrap();
+        let map = input_to_seat_map("F\nB\n", &layout).unwrap();
+
+        assert_eq!(map.seat_ids(), vec![SeatId(0), SeatId(u64::MAX)]);
+        assert!(map.gaps().is_empty());
+    }
+
+    #[test]
+    fn test_seat_map_gaps_multiplier() {
+        // IDs are 0, 1 then 4, 5, so 2 and 3 have no seats
+        let layout = SeatLayout::try_from("2x2:4").unwrap();
+
+        let input = "FL\nBR\n";
+        let map = input_to_seat_map(input, &layout).unwrap();
+
+        assert_eq!(map.seat_ids(), vec![SeatId(0), SeatId(5)]);
+        assert!(map.gaps().is_empty());
+
+        // ID 4 has no seat, so the empty seat 3 next to it isn't a gap
+        let layout = SeatLayout::try_from("2x4:5").unwrap();
+
+        let input = "FLL\nFRL\nBLL\n";
+        let map = input_to_seat_map(input, &layout).unwrap();
+
+        assert_eq!(map.seat_ids(), vec![SeatId(0), SeatId(2), SeatId(5)]);
+        assert_eq!(map.gaps(), vec![SeatId(1)]);
+    }
+
+    #[test]
+    fn test_seat_map_gaps() {
+        let layout = SeatLayout::new(4, 4, 4).unwrap();
+
+        // Seats 0 and 15 are empty on the ends, 9 and 12 are gaps, 5 and 6
+        // are adjacent empty seats so neither is a gap.
+        let seats = vec![
+            "FFLR", "FFRL", "FFRR",
+            "FBLL", "FBRR",
+            "BFLL", "BFRL", "BFRR",
+            "BBLR", "BBRL",
+        ];
+
+        let input = seats.join("\n");
+        let map = input_to_seat_map(&input, &layout).unwrap();
+
+        assert_eq!(map.gaps(), vec![SeatId(9), SeatId(12)]);
+        assert_eq!(map.seat_ids().len(), 10);
+
+        let expected = concat!(
+            "   0 .###\n",
+            "   1 #..#\n",
+            "   2 #.##\n",
+            "   3 .##.\n",
+        );
+
+        assert_eq!(map.to_string(), expected);
+    }
+
+    #[test]
+    fn test_seat_map_duplicate() {
+        let input = "FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRLR\n";
+        let err = input_to_seat_map(input, &SeatLayout::default())
+            .unwrap_err();
+
+        let expected = "Line 3: Duplicate boarding pass FBFBBFFRLR";
+
+        assert_eq!(err.to_string(), expected);
+    }
 }
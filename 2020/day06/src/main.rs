@@ -1,7 +1,10 @@
 // day
-use std::collections::HashMap;
+use common::input::numbered_records;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{
     self,
@@ -12,13 +15,106 @@ use std::io::{
 // CLI arguments
 type Args = Vec<String>;
 
-// Vec of questions a person answered yes to
+// Number of questions on the customs declaration form, 'a' to 'z'.
+const QUESTIONS: u32 = 26;
+
+// An answer that isn't one of the questions
+#[derive(Debug, PartialEq)]
+struct UnknownQuestion(char);
+
+impl fmt::Display for UnknownQuestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown question '{}'", self.0)
+    }
+}
+
+impl Error for UnknownQuestion {}
+
+// Set of questions answered yes to, one bit per question with 'a' in the
+// lowest bit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Answers(u32);
+
+impl Answers {
+    // Every question answered yes
+    fn all() -> Self {
+        Self((1 << QUESTIONS) - 1)
+    }
+
+    fn bit(question: char) -> Result<u32, UnknownQuestion> {
+        match question {
+            'a' ..= 'z' => Ok(1 << (question as u32 - 'a' as u32)),
+            _           => Err(UnknownQuestion(question)),
+        }
+    }
+
+    fn insert(&mut self, question: char) -> Result<(), UnknownQuestion> {
+        self.0 |= Self::bit(question)?;
+
+        Ok(())
+    }
+
+    // Unknown questions are never answered
+    fn contains(&self, question: char) -> bool {
+        Self::bit(question).is_ok_and(|bit| self.0 & bit != 0)
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    // Questions in this set but not the other
+    #[allow(dead_code)]
+    fn difference(&self, other: &Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    // Number of questions in the set
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    // Iterate over the questions in the set, in order
+    #[allow(dead_code)]
+    fn questions(&self) -> impl Iterator<Item = char> + '_ {
+        ('a' ..= 'z').filter(move |c| self.contains(*c))
+    }
+}
+
+// Whitespace around the answers is ignored
+impl TryFrom<&str> for Answers {
+    type Error = UnknownQuestion;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let mut answers: Self = Default::default();
+
+        for c in input.trim().chars() {
+            answers.insert(c)?;
+        }
+
+        Ok(answers)
+    }
+}
+
+// Questions a person answered yes to
 #[derive(Clone, Debug, Default)]
-struct Person(Vec<char>);
+struct Person(Answers);
 
 impl Person {
-    fn push(&mut self, c: char) {
-        self.0.push(c);
+    fn answers(&self) -> &Answers {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for Person {
+    type Error = UnknownQuestion;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Ok(Self(Answers::try_from(input)?))
     }
 }
 
@@ -31,27 +127,37 @@ impl Group {
         self.0.push(person.clone());
     }
 
-    // Return a count of how many questions each person in a group had in
-    // common
-    fn same_answers(&self) -> usize {
-        let mut counter: HashMap<&char, usize> = HashMap::new();
+    // Questions anyone in the group answered yes to
+    fn union(&self) -> Answers {
+        self.0.iter()
+            .fold(Answers::default(), |acc, p| acc.union(p.answers()))
+    }
 
-        for person in &self.0 {
-            for c in &person.0 {
-                *counter.entry(c).or_insert(0) += 1;
-            }
+    // Questions everyone in the group answered yes to
+    fn intersection(&self) -> Answers {
+        if self.0.is_empty() {
+            return Answers::default();
         }
 
-        let group_size = self.0.len();
-        let mut same_answers = 0;
-
-        for (_, num) in counter.iter() {
-            if *num == group_size {
-                same_answers += 1;
-            }
-        }
+        self.0.iter()
+            .fold(Answers::all(), |acc, p| acc.intersection(p.answers()))
+    }
 
-        same_answers
+    // Questions answered yes to by at least k people in the group
+    #[allow(dead_code)]
+    fn answered_by_at_least(&self, k: usize) -> Answers {
+        let bits = (0 .. QUESTIONS)
+            .map(|question| 1 << question)
+            .filter(|bit| {
+                let count = self.0.iter()
+                    .filter(|p| p.answers().0 & bit != 0)
+                    .count();
+
+                count >= k
+            })
+            .fold(0, |acc, bit| acc | bit);
+
+        Answers(bits)
     }
 }
 
@@ -63,13 +169,157 @@ impl Groups {
         self.0.push(group.clone());
     }
 
-    fn same_answers(&self) -> usize {
+    fn stats(&self) -> Stats {
+        let questions = ('a' ..= 'z')
+            .map(|question| {
+                let mut stats = QuestionStats {
+                    question,
+                    people:   0,
+                    groups:   0,
+                };
+
+                for group in &self.0 {
+                    let people = group.0.iter()
+                        .filter(|p| p.answers().contains(question))
+                        .count();
+
+                    if people > 0 {
+                        stats.people += people;
+                        stats.groups += 1;
+                    }
+                }
+
+                stats
+            })
+            .collect();
+
+        let mut group_sizes = BTreeMap::new();
+
+        for group in &self.0 {
+            *group_sizes.entry(group.0.len()).or_insert(0) += 1;
+        }
+
+        Stats {
+            questions,
+            group_sizes,
+        }
+    }
+
+    // Sum the number of questions selected by f over every group
+    fn count<F>(&self, f: F) -> usize
+    where
+        F: Fn(&Group) -> Answers,
+    {
         self.0.iter()
-            .map(|g| g.same_answers())
+            .map(|g| f(g).len())
             .sum()
     }
 }
 
+// Yes answers for a single question across all groups
+#[derive(Debug, PartialEq)]
+struct QuestionStats {
+    question: char,
+    people:   usize,
+    groups:   usize,
+}
+
+// Statistics over every group, for sanity checking inputs
+#[derive(Debug)]
+struct Stats {
+    // One entry per question, in question order
+    questions: Vec<QuestionStats>,
+
+    // Map of group size to the number of groups of that size
+    group_sizes: BTreeMap<usize, usize>,
+}
+
+impl Stats {
+    // Questions answered yes to by the most people, ignoring questions
+    // nobody answered.
+    fn most_common(&self) -> Vec<char> {
+        let max = self.questions.iter()
+            .map(|q| q.people)
+            .filter(|people| *people > 0)
+            .max();
+
+        match max {
+            Some(max) => self.with_people(max),
+            None      => Vec::new(),
+        }
+    }
+
+    // Questions answered yes to by the fewest people, ignoring questions
+    // nobody answered.
+    fn least_common(&self) -> Vec<char> {
+        let min = self.questions.iter()
+            .map(|q| q.people)
+            .filter(|people| *people > 0)
+            .min();
+
+        match min {
+            Some(min) => self.with_people(min),
+            None      => Vec::new(),
+        }
+    }
+
+    fn with_people(&self, people: usize) -> Vec<char> {
+        self.questions.iter()
+            .filter(|q| q.people == people)
+            .map(|q| q.question)
+            .collect()
+    }
+
+    fn questions_csv(&self) -> String {
+        let mut csv = String::from("question,people,groups\n");
+
+        for q in &self.questions {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                q.question,
+                q.people,
+                q.groups,
+            ));
+        }
+
+        csv
+    }
+
+    fn group_sizes_csv(&self) -> String {
+        let mut csv = String::from("group_size,groups\n");
+
+        for (size, groups) in &self.group_sizes {
+            csv.push_str(&format!("{},{}\n", size, groups));
+        }
+
+        csv
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Question  People  Groups")?;
+
+        for q in &self.questions {
+            writeln!(f, "{:<8}  {:>6}  {:>6}", q.question, q.people, q.groups)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Group size  Groups")?;
+
+        for (size, groups) in &self.group_sizes {
+            writeln!(f, "{:>10}  {:>6}", size, groups)?;
+        }
+
+        let most: String = self.most_common().into_iter().collect();
+        let least: String = self.least_common().into_iter().collect();
+
+        writeln!(f)?;
+        writeln!(f, "Most common: {}", most)?;
+        write!(f, "Least common: {}", least)
+    }
+}
+
 // Get an input reader
 fn input_reader(
     args: Args,
@@ -90,83 +340,90 @@ fn input_reader(
     Ok(reader)
 }
 
-fn input_to_part_one_questions(input: &str) -> Vec<usize> {
-    let mut yes: Vec<usize> = Vec::new();
-    let mut tmp: Vec<char> = Vec::new();
-
-    for line in input.lines() {
-        if line.len() > 0 {
-            for c in line.chars() {
-                tmp.push(c);
-            }
-        }
-        else {
-            tmp.sort();
-            tmp.dedup();
-            yes.push(tmp.len());
-            tmp = Vec::new();
-        }
-    }
-
-    tmp.sort();
-    tmp.dedup();
-    yes.push(tmp.len());
-
-    yes
-}
-
-fn input_to_part_two_questions(input: &str) -> Groups {
+fn input_to_groups(input: &str) -> Result<Groups, Box<dyn Error>> {
     let mut groups: Groups = Default::default();
-    let mut group: Group = Default::default();
 
-    for line in input.lines() {
-        if line.len() > 0 {
-            // Each line is a new person
-            let mut person: Person = Default::default();
+    for record in numbered_records(input) {
+        let mut group: Group = Default::default();
 
-            for c in line.chars() {
-                person.push(c)
-            }
+        // Each line is a new person
+        for (number, line) in record {
+            let person = Person::try_from(line)
+                .map_err(|e| format!("Line {}: {}", number, e))?;
 
             group.push(&person);
         }
-        else {
-            // A blank line is the end of a group of people and we can add the
-            // group to the groups.
-            groups.push(&group);
-            group = Default::default();
-        }
+
+        groups.push(&group);
     }
 
-    groups.push(&group);
-    groups
+    Ok(groups)
 }
 
-fn part_one(input: &str) {
-    let questions = input_to_part_one_questions(&input);
-    let total: usize = questions.iter().sum();
+fn part_one(groups: &Groups) {
+    let total = groups.count(Group::union);
 
     println!("Part 1: Yes = {}", total);
 }
 
-fn part_two(input: &str) {
-    let groups = input_to_part_two_questions(&input);
-    let count = groups.same_answers();
+fn part_two(groups: &Groups) {
+    let count = groups.count(Group::intersection);
 
     println!("Part 2: {}", count);
 }
 
+// Reports that can be shown instead of the puzzle answers
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Report {
+    Stats,
+    QuestionsCsv,
+    SizesCsv,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    let mut report = None;
+
+    for flag in &flags {
+        if flag == "--stats" {
+            report = Some(Report::Stats);
+        }
+        else if flag == "--csv=questions" {
+            report = Some(Report::QuestionsCsv);
+        }
+        else if flag == "--csv=sizes" {
+            report = Some(Report::SizesCsv);
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
     // Get the input
     let mut buffer = String::new();
     let mut reader = input_reader(args)?;
     reader.read_to_string(&mut buffer)?;
 
-
-    part_one(&buffer);
-    part_two(&buffer);
+    let groups = input_to_groups(&buffer)?;
+
+    match report {
+        None => {
+            part_one(&groups);
+            part_two(&groups);
+        },
+        Some(Report::Stats) => {
+            println!("{}", groups.stats());
+        },
+        Some(Report::QuestionsCsv) => {
+            print!("{}", groups.stats().questions_csv());
+        },
+        Some(Report::SizesCsv) => {
+            print!("{}", groups.stats().group_sizes_csv());
+        },
+    }
 
     Ok(())
 }
@@ -174,4 +431,88 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    // Taken from example on day 6
+    const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+    #[test]
+    fn test_answers_set_algebra() {
+        let answers = |s| Answers::try_from(s).unwrap();
+        let a = answers("abc");
+        let b = answers("bcd");
+
+        assert_eq!(a.union(&b), answers("abcd"));
+        assert_eq!(a.intersection(&b), answers("bc"));
+        assert_eq!(a.difference(&b), answers("a"));
+        assert_eq!(a.questions().collect::<String>(), "abc");
+        assert_eq!(Answers::all().len(), 26);
+    }
+
+    #[test]
+    fn test_part_one() {
+        let groups = input_to_groups(EXAMPLE).unwrap();
+
+        assert_eq!(groups.count(Group::union), 11);
+    }
+
+    #[test]
+    fn test_part_two() {
+        let groups = input_to_groups(EXAMPLE).unwrap();
+
+        assert_eq!(groups.count(Group::intersection), 6);
+    }
+
+    #[test]
+    fn test_group_queries() {
+        let groups = input_to_groups(EXAMPLE).unwrap();
+        let group = &groups.0[2];
+
+        assert_eq!(group.answered_by_at_least(1), group.union());
+        assert_eq!(group.answered_by_at_least(2), group.intersection());
+        assert_eq!(group.answered_by_at_least(3), Answers::default());
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = input_to_groups(EXAMPLE).unwrap().stats();
+
+        assert_eq!(stats.questions[0], QuestionStats {
+            question: 'a',
+            people:   8,
+            groups:   4,
+        });
+
+        assert_eq!(stats.most_common(), vec!['a']);
+        assert_eq!(stats.least_common(), vec!['c']);
+
+        let expected = "group_size,groups\n1,2\n2,1\n3,1\n4,1\n";
+        assert_eq!(stats.group_sizes_csv(), expected);
+
+        let csv = stats.questions_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("question,people,groups"));
+        assert_eq!(lines.next(), Some("a,8,4"));
+        assert_eq!(lines.next(), Some("b,4,4"));
+        assert_eq!(lines.next(), Some("c,3,3"));
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        let stats = input_to_groups("").unwrap().stats();
+
+        assert!(stats.most_common().is_empty());
+        assert!(stats.least_common().is_empty());
+        assert!(stats.group_sizes.is_empty());
+    }
+
+    #[test]
+    fn test_input_to_groups_invalid() {
+        let groups = input_to_groups("ab \n\nc\n").unwrap();
+
+        assert_eq!(groups.count(Group::union), 3);
+
+        let err = input_to_groups("ab\n\nc\nd1\n").unwrap_err();
+
+        assert_eq!(err.to_string(), "Line 4: Unknown question '1'");
+    }
 }
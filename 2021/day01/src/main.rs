@@ -1,4 +1,6 @@
 // day
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -31,68 +33,128 @@ fn input_reader(
     Ok(reader)
 }
 
-fn str_to_nums(input: &str) -> Vec<usize> {
-    input.lines()
-        .map(|s| s.parse().expect("valid integer"))
-        .collect()
+// How the sum of each window compared to the window before it
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    increases: usize,
+    decreases: usize,
+    unchanged: usize,
 }
 
-fn part_one(input: &[usize]) {
-    let mut increases = 0;
-
-    // Get the starting point for depth
-    let mut depth = input[0];
+// Compare the sums of each sliding window of readings with the previous
+// window. Consecutive windows share all but one reading, so comparing the
+// sums is the same as comparing the reading entering the window with the one
+// leaving it. Only the readings in the current window are kept.
+//
+// Input shorter than window + 1 readings has nothing to compare, and a
+// window of 0 has no readings to sum, so both give no changes.
+fn count_increases<I>(readings: I, window: usize) -> Changes
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut changes: Changes = Default::default();
+
+    if window == 0 {
+        return changes;
+    }
 
-    // Skip the first element, we already took it above
-    for num in input.iter().skip(1) {
-        if *num > depth {
-            increases += 1;
+    let mut current = VecDeque::new();
+
+    for reading in readings {
+        if current.len() == window {
+            if let Some(leaving) = current.pop_front() {
+                match reading.cmp(&leaving) {
+                    Ordering::Greater => changes.increases += 1,
+                    Ordering::Less    => changes.decreases += 1,
+                    Ordering::Equal   => changes.unchanged += 1,
+                }
+            }
         }
 
-        depth = *num;
+        current.push_back(reading);
     }
 
-    println!("Part 1 increases: {}", increases);
+    changes
 }
 
-fn part_two(input: &[usize]) {
-    let mut increases = 0;
+// Count the changes in the readings, one per line, parsing each reading as
+// it's compared. Stops at the first reading that isn't a number.
+fn input_changes(
+    input: &str,
+    window: usize,
+) -> Result<Changes, Box<dyn Error>> {
+    let mut error = None;
+
+    let readings = input.lines()
+        .enumerate()
+        .map_while(|(i, line)| {
+            match line.trim().parse() {
+                Ok(reading) => Some(reading),
+                Err(e)      => {
+                    error = Some(format!("Line {}: {}: {}", i + 1, line, e));
+                    None
+                },
+            }
+        });
+
+    let changes = count_increases(readings, window);
+
+    match error {
+        Some(error) => Err(error.into()),
+        None        => Ok(changes),
+    }
+}
 
-    // Get the first set of values and sum them for future comparison
-    let mut depth = input.get(0..3)
-        .unwrap()
-        .iter()
-        .sum();
+fn part_one(input: &str) -> Result<(), Box<dyn Error>> {
+    let changes = input_changes(input, 1)?;
 
-    // Index for getting our numbers
-    let mut n = 1;
+    println!("Part 1 increases: {}", changes.increases);
 
-    while let Some(nums) = input.get(n .. n + 3) {
-        let sum: usize = nums.iter().sum();
+    Ok(())
+}
 
-        if sum > depth {
-            increases += 1;
-        }
+fn part_two(input: &str) -> Result<(), Box<dyn Error>> {
+    let changes = input_changes(input, 3)?;
 
-        depth = sum;
-        n += 1;
-    }
+    println!("Part 2 increases: {}", changes.increases);
 
-    println!("Part 2 increases: {}", increases);
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = env::args().collect();
+    // Split --flags from the positional arguments
+    let (flags, args): (Args, Args) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+
+    // Report every kind of change for a single window size
+    let mut window = None;
+
+    for flag in &flags {
+        if let Some(n) = flag.strip_prefix("--window=") {
+            window = Some(n.parse()?);
+        }
+        else {
+            return Err(format!("Unknown flag: {}", flag).into());
+        }
+    }
 
     // Get the input
     let mut buffer = String::new();
     let mut reader = input_reader(args)?;
     reader.read_to_string(&mut buffer)?;
 
-    let nums = str_to_nums(&buffer);
+    if let Some(window) = window {
+        let changes = input_changes(&buffer, window)?;
+
+        println!("Increases: {}", changes.increases);
+        println!("Decreases: {}", changes.decreases);
+        println!("Unchanged: {}", changes.unchanged);
+
+        return Ok(());
+    }
 
-    part_one(&nums);
-    part_two(&nums);
+    part_one(&buffer)?;
+    part_two(&buffer)?;
 
     Ok(())
 }
@@ -100,4 +162,62 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    // Taken from example on day 1
+    const EXAMPLE: [usize; 10] = [
+        199,
+        200,
+        208,
+        210,
+        200,
+        207,
+        240,
+        269,
+        260,
+        263,
+    ];
+
+    #[test]
+    fn test_count_increases() {
+        let changes = count_increases(EXAMPLE, 1);
+
+        assert_eq!(changes, Changes {
+            increases: 7,
+            decreases: 2,
+            unchanged: 0,
+        });
+
+        let changes = count_increases(EXAMPLE, 3);
+
+        assert_eq!(changes, Changes {
+            increases: 5,
+            decreases: 1,
+            unchanged: 1,
+        });
+    }
+
+    #[test]
+    fn test_count_increases_short_input() {
+        assert_eq!(count_increases(vec![], 1), Changes::default());
+        assert_eq!(count_increases(vec![1], 1), Changes::default());
+        assert_eq!(count_increases(vec![1, 2, 3], 3), Changes::default());
+        assert_eq!(count_increases(EXAMPLE, 10), Changes::default());
+        assert_eq!(count_increases(EXAMPLE, usize::MAX), Changes::default());
+        assert_eq!(count_increases(EXAMPLE, 0), Changes::default());
+        assert_eq!(count_increases(EXAMPLE, 9).increases, 1);
+    }
+
+    #[test]
+    fn test_input_changes() {
+        let input = "199\n200\n208\n210\n";
+
+        assert_eq!(input_changes(input, 1).unwrap().increases, 3);
+
+        let err = input_changes("199\n200\nabc\n210\n", 1).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Line 3: abc: invalid digit found in string",
+        );
+    }
 }
@@ -0,0 +1,420 @@
+// 2D grid
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+// Offsets to the 4 orthogonal neighbours, as (row, col)
+const NEIGHBOURS_4: [(isize, isize); 4] = [
+    (-1, 0),
+    (0, -1),
+    (0, 1),
+    (1, 0),
+];
+
+// Offsets to the 8 neighbours including diagonals, as (row, col)
+const NEIGHBOURS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum GridError {
+    // A row had a different number of columns to the first row. The row
+    // is counted from 0.
+    Ragged {
+        row:      usize,
+        expected: usize,
+        found:    usize,
+    },
+
+    // The grid has more cells than can be addressed
+    TooLarge {
+        rows: usize,
+        cols: usize,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ragged { row, expected, found } => {
+                write!(
+                    f,
+                    "Line {} has {} columns, expected {}",
+                    row + 1,
+                    found,
+                    expected,
+                )
+            },
+            Self::TooLarge { rows, cols } => {
+                write!(f, "Grid of {}x{} cells is too large", rows, cols)
+            },
+        }
+    }
+}
+
+impl Error for GridError {}
+
+// Which axes of the grid wrap around when accessed out of bounds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Wrapping {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Wrapping {
+    fn horizontal(&self) -> bool {
+        matches!(self, Self::Horizontal | Self::Both)
+    }
+
+    fn vertical(&self) -> bool {
+        matches!(self, Self::Vertical | Self::Both)
+    }
+}
+
+// A grid of cells stored row by row in a single Vec.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Grid<T> {
+    cells:    Vec<T>,
+    rows:     usize,
+    cols:     usize,
+    wrapping: Wrapping,
+}
+
+impl<T: Clone> Grid<T> {
+    // A grid of the given size with every cell set to value. Fails if the
+    // cells can't be addressed.
+    pub fn new(rows: usize, cols: usize, value: T) -> Result<Self, GridError> {
+        let too_large = GridError::TooLarge { rows, cols };
+
+        // Positions are resolved as isize, so every cell must fit in one
+        let size = rows.checked_mul(cols)
+            .filter(|size| isize::try_from(*size).is_ok())
+            .ok_or(too_large)?;
+
+        Ok(Self {
+            cells:    vec![value; size],
+            rows,
+            cols,
+            wrapping: Wrapping::None,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    // Parse a grid from lines of text, mapping each character to a cell.
+    pub fn parse<F>(input: &str, f: F) -> Result<Self, GridError>
+    where
+        F: Fn(char) -> T,
+    {
+        let mut cells = Vec::new();
+        let mut rows = 0;
+        let mut cols = 0;
+
+        for (row, line) in input.lines().enumerate() {
+            let before = cells.len();
+            cells.extend(line.chars().map(&f));
+            let found = cells.len() - before;
+
+            if row == 0 {
+                cols = found;
+            }
+            else if found != cols {
+                return Err(GridError::Ragged {
+                    row,
+                    expected: cols,
+                    found,
+                });
+            }
+
+            rows += 1;
+        }
+
+        Ok(Self {
+            cells,
+            rows,
+            cols,
+            wrapping: Wrapping::None,
+        })
+    }
+
+    // Set the wrapping policy for the grid
+    pub fn with_wrapping(mut self, wrapping: Wrapping) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Resolve a possibly out of bounds position to an index into cells,
+    // applying the wrapping policy.
+    fn index(&self, row: isize, col: isize) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let resolve = |n: isize, len: usize, wrap: bool| {
+            let len = len as isize;
+
+            if wrap {
+                Some(n.rem_euclid(len) as usize)
+            }
+            else if (0 .. len).contains(&n) {
+                Some(n as usize)
+            }
+            else {
+                None
+            }
+        };
+
+        let row = resolve(row, self.rows, self.wrapping.vertical())?;
+        let col = resolve(col, self.cols, self.wrapping.horizontal())?;
+
+        Some((row * self.cols) + col)
+    }
+
+    // As index, for unsigned positions. Positions too large for an isize
+    // are out of bounds.
+    fn index_unsigned(&self, row: usize, col: usize) -> Option<usize> {
+        let row = isize::try_from(row).ok()?;
+        let col = isize::try_from(col).ok()?;
+
+        self.index(row, col)
+    }
+
+    // Get the cell at a position, None if it's out of bounds on an axis that
+    // doesn't wrap.
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        let index = self.index_unsigned(row, col)?;
+        self.cells.get(index)
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        let index = self.index_unsigned(row, col)?;
+        self.cells.get_mut(index)
+    }
+
+    // As get, but allowing negative positions
+    pub fn get_signed(&self, row: isize, col: isize) -> Option<&T> {
+        let index = self.index(row, col)?;
+        self.cells.get(index)
+    }
+
+    // Set the cell at a position, returning false if it's out of bounds.
+    pub fn set(&mut self, row: usize, col: usize, value: T) -> bool {
+        match self.get_mut(row, col) {
+            Some(cell) => {
+                *cell = value;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        row: usize,
+        col: usize,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize, &'a T)> + 'a {
+        offsets.iter().filter_map(move |(dr, dc)| {
+            let row = isize::try_from(row).ok()?.checked_add(*dr)?;
+            let col = isize::try_from(col).ok()?.checked_add(*dc)?;
+            let index = self.index(row, col)?;
+
+            Some((index / self.cols, index % self.cols, &self.cells[index]))
+        })
+    }
+
+    // The up to 4 orthogonal neighbours of a position as (row, col, cell)
+    pub fn neighbours_4(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> impl Iterator<Item = (usize, usize, &T)> {
+        self.neighbours(row, col, &NEIGHBOURS_4)
+    }
+
+    // The up to 8 neighbours of a position, including diagonals
+    pub fn neighbours_8(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> impl Iterator<Item = (usize, usize, &T)> {
+        self.neighbours(row, col, &NEIGHBOURS_8)
+    }
+
+    // A single row of the grid
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row >= self.rows {
+            return None;
+        }
+
+        let start = row * self.cols;
+
+        Some(&self.cells[start .. start + self.cols])
+    }
+
+    // Cells in a single column, top to bottom
+    pub fn col(&self, col: usize) -> impl Iterator<Item = &T> {
+        let cols = self.cols;
+
+        self.cells.iter()
+            .skip(col)
+            .step_by(cols.max(1))
+            .take(if col < cols { self.rows } else { 0 })
+    }
+
+    // Every row of the grid, top to bottom
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.cols.max(1))
+    }
+
+    // Every cell in the grid as (row, col, cell), row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let cols = self.cols;
+
+        self.cells.iter()
+            .enumerate()
+            .map(move |(i, cell)| (i / cols, i % cols, cell))
+    }
+}
+
+impl<T: From<char>> TryFrom<&str> for Grid<T> {
+    type Error = GridError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input, T::from)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.iter_rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "abc\ndef\n";
+
+    #[test]
+    fn test_grid_parse() {
+        let grid = Grid::parse(EXAMPLE, |c| c).unwrap();
+
+        assert_eq!(grid.rows(), 2);
+        assert_eq!(grid.cols(), 3);
+        assert_eq!(grid.get(1, 2), Some(&'f'));
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.row(1), Some(&['d', 'e', 'f'][..]));
+        assert_eq!(grid.col(1).collect::<String>(), "be");
+        assert_eq!(grid.to_string(), EXAMPLE);
+
+        let ragged = Grid::parse("abc\nde\n", |c| c);
+        let expected = GridError::Ragged {
+            row:      1,
+            expected: 3,
+            found:    2,
+        };
+
+        assert_eq!(ragged, Err(expected));
+        assert_eq!(
+            ragged.unwrap_err().to_string(),
+            "Line 2 has 2 columns, expected 3",
+        );
+    }
+
+    #[test]
+    fn test_grid_empty() {
+        let grid = Grid::parse("", |c| c)
+            .unwrap()
+            .with_wrapping(Wrapping::Both);
+
+        assert!(grid.is_empty());
+        assert_eq!(grid.get(0, 0), None);
+        assert_eq!(grid.col(0).count(), 0);
+        assert_eq!(grid.iter_rows().count(), 0);
+    }
+
+    #[test]
+    fn test_grid_wrapping() {
+        let grid = Grid::parse(EXAMPLE, |c| c).unwrap();
+
+        let horizontal = grid.clone().with_wrapping(Wrapping::Horizontal);
+        assert_eq!(horizontal.get(0, 4), Some(&'b'));
+        assert_eq!(horizontal.get_signed(1, -1), Some(&'f'));
+        assert_eq!(horizontal.get(2, 0), None);
+
+        let vertical = grid.clone().with_wrapping(Wrapping::Vertical);
+        assert_eq!(vertical.get(2, 0), Some(&'a'));
+        assert_eq!(vertical.get(0, 4), None);
+
+        // Too large for an isize, rather than wrapping to a negative
+        assert_eq!(horizontal.get(0, usize::MAX), None);
+        assert_eq!(horizontal.get(0, isize::MAX as usize), Some(&'b'));
+
+        let both = grid.with_wrapping(Wrapping::Both);
+        assert_eq!(both.get_signed(-1, -1), Some(&'f'));
+    }
+
+    #[test]
+    fn test_grid_neighbours() {
+        let mut grid = Grid::new(3, 3, 0).unwrap();
+        grid.set(1, 1, 5);
+        assert!(!grid.set(3, 0, 1));
+
+        let corner: Vec<(usize, usize)> = grid.neighbours_4(0, 0)
+            .map(|(row, col, _)| (row, col))
+            .collect();
+
+        assert_eq!(corner, vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours_8(0, 0).count(), 3);
+        assert_eq!(grid.neighbours_8(1, 1).count(), 8);
+        assert_eq!(grid.neighbours_8(0, 1).map(|(_, _, v)| v).sum::<i32>(), 5);
+
+        let grid = grid.with_wrapping(Wrapping::Both);
+        assert_eq!(grid.neighbours_8(0, 0).count(), 8);
+    }
+
+    #[test]
+    fn test_grid_too_large() {
+        let expected = GridError::TooLarge {
+            rows: usize::MAX,
+            cols: 2,
+        };
+
+        assert_eq!(Grid::new(usize::MAX, 2, 0), Err(expected));
+        assert!(Grid::new(usize::MAX / 2, 2, 0).is_err());
+        assert_eq!(Grid::new(0, usize::MAX, 0).map(|g| g.is_empty()), Ok(true));
+    }
+}
@@ -0,0 +1,224 @@
+// Input helpers
+use std::str::SplitInclusive;
+
+// Options controlling how grouped records are split.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupOptions {
+    // Strip a trailing '\r' from each line, allowing CRLF input.
+    pub strip_cr: bool,
+
+    // Treat runs of blank lines as a single separator. When disabled, each
+    // additional blank line produces an empty group.
+    pub collapse_blank_lines: bool,
+
+    // Emit the final group even if the input doesn't end with a blank line.
+    pub trailing_group: bool,
+}
+
+impl Default for GroupOptions {
+    fn default() -> Self {
+        Self {
+            strip_cr:             true,
+            collapse_blank_lines: true,
+            trailing_group:       true,
+        }
+    }
+}
+
+// Iterator over groups of lines separated by blank lines, with each line
+// paired with its line number, starting from 1.
+#[derive(Debug)]
+pub struct NumberedRecords<'a> {
+    lines:   SplitInclusive<'a, char>,
+    options: GroupOptions,
+    line:    usize,
+    done:    bool,
+}
+
+impl<'a> NumberedRecords<'a> {
+    pub fn new(input: &'a str, options: GroupOptions) -> Self {
+        Self {
+            lines:   input.split_inclusive('\n'),
+            options,
+            line:    0,
+            done:    false,
+        }
+    }
+
+    // Get the next line, applying any line ending options.
+    fn next_line(&mut self) -> Option<&'a str> {
+        let line = self.lines.next()?;
+        let line = line.strip_suffix('\n').unwrap_or(line);
+
+        self.line += 1;
+
+        if self.options.strip_cr {
+            Some(line.strip_suffix('\r').unwrap_or(line))
+        }
+        else {
+            Some(line)
+        }
+    }
+}
+
+impl<'a> Iterator for NumberedRecords<'a> {
+    type Item = Vec<(usize, &'a str)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut group = Vec::new();
+
+        loop {
+            match self.next_line() {
+                Some("") => {
+                    // A blank line ends the group, unless we haven't started
+                    // one yet and we're collapsing runs of blank lines.
+                    if group.is_empty() && self.options.collapse_blank_lines {
+                        continue;
+                    }
+
+                    return Some(group);
+                },
+                Some(line) => group.push((self.line, line)),
+                None => {
+                    // End of input. An empty group here is just the input
+                    // ending on a blank line.
+                    self.done = true;
+
+                    if group.is_empty() || !self.options.trailing_group {
+                        return None;
+                    }
+
+                    return Some(group);
+                },
+            }
+        }
+    }
+}
+
+// Iterator over groups of lines separated by blank lines.
+#[derive(Debug)]
+pub struct GroupedRecords<'a>(NumberedRecords<'a>);
+
+impl<'a> GroupedRecords<'a> {
+    pub fn new(input: &'a str, options: GroupOptions) -> Self {
+        Self(NumberedRecords::new(input, options))
+    }
+}
+
+impl<'a> Iterator for GroupedRecords<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let group = self.0.next()?;
+
+        Some(group.into_iter().map(|(_, line)| line).collect())
+    }
+}
+
+// Split input into groups of lines separated by blank lines.
+pub fn grouped_records(input: &str) -> GroupedRecords<'_> {
+    GroupedRecords::new(input, GroupOptions::default())
+}
+
+// As grouped_records, but with the given options.
+pub fn grouped_records_with(
+    input: &str,
+    options: GroupOptions,
+) -> GroupedRecords<'_> {
+    GroupedRecords::new(input, options)
+}
+
+// As grouped_records, but pairing each line with its line number.
+pub fn numbered_records(input: &str) -> NumberedRecords<'_> {
+    NumberedRecords::new(input, GroupOptions::default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grouped_records() {
+        let input = "a\nb\n\nc\n\n\nd\ne\n";
+        let groups: Vec<Vec<&str>> = grouped_records(input).collect();
+
+        let expected = vec![
+            vec!["a", "b"],
+            vec!["c"],
+            vec!["d", "e"],
+        ];
+
+        assert_eq!(groups, expected);
+    }
+
+    #[test]
+    fn test_grouped_records_crlf() {
+        let input = "a\r\nb\r\n\r\nc\r\n";
+        let groups: Vec<Vec<&str>> = grouped_records(input).collect();
+
+        assert_eq!(groups, vec![vec!["a", "b"], vec!["c"]]);
+
+        let options = GroupOptions {
+            strip_cr: false,
+            ..Default::default()
+        };
+
+        let groups: Vec<Vec<&str>> = grouped_records_with(input, options)
+            .collect();
+
+        assert_eq!(groups, vec![vec!["a\r", "b\r", "\r", "c\r"]]);
+    }
+
+    #[test]
+    fn test_grouped_records_final_newline() {
+        let options = GroupOptions {
+            collapse_blank_lines: false,
+            trailing_group:       false,
+            ..Default::default()
+        };
+
+        let input = "a\n\nb\n";
+        let groups: Vec<Vec<&str>> = grouped_records_with(input, options)
+            .collect();
+
+        assert_eq!(groups, vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn test_grouped_records_options() {
+        let input = "a\n\n\nb\n\nc";
+
+        let options = GroupOptions {
+            collapse_blank_lines: false,
+            ..Default::default()
+        };
+
+        let groups: Vec<Vec<&str>> = grouped_records_with(input, options)
+            .collect();
+
+        assert_eq!(groups, vec![vec!["a"], vec![], vec!["b"], vec!["c"]]);
+
+        let options = GroupOptions {
+            trailing_group: false,
+            ..Default::default()
+        };
+
+        let groups: Vec<Vec<&str>> = grouped_records_with(input, options)
+            .collect();
+
+        assert_eq!(groups, vec![vec!["a"], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_numbered_records() {
+        let input = "a\nb\n\n\nc\r\n";
+        let groups: Vec<Vec<(usize, &str)>> = numbered_records(input)
+            .collect();
+
+        assert_eq!(groups, vec![vec![(1, "a"), (2, "b")], vec![(5, "c")]]);
+    }
+}
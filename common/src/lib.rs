@@ -0,0 +1,4 @@
+// common
+// Code shared between the days.
+pub mod grid;
+pub mod input;